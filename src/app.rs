@@ -1,20 +1,58 @@
+use crossterm::event::KeyEvent;
+use impl_helper::ImplHelper;
 use itertools::Itertools;
 use ratatui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Flex, Layout, Rect, Size},
-    style::{Color, Style, Stylize},
+    layout::{Alignment, Constraint, Flex, Layout, Position, Rect, Size},
+    style::{Style, Stylize},
     widgets::Clear,
     Frame,
 };
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use std::{
+    cell::RefCell,
+    io::stdout,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 use wrap_context::{arg_context, liab, raw_context};
 
 use crate::{
-    column_model::*, directional_constraint::*, event::*, handler::*, node::*, page_model::*,
-    page_view::*, paginator::*, preferences::*, render::*, table_model::*, textline_model::*,
-    textline_view::*, tui::*, types::*, utils::*,
+    column_model::*, diff::*, directional_constraint::*, edit::*, event::*, flake_lock::*,
+    handler::*, help_model::*, help_view::*, keymap::*, node::*, output::*, page_model::*,
+    page_view::*, preferences::*, prompt::*, query::*, render::*, search::*, table_model::*,
+    textline_model::*, textline_view::*, theme::*, tui::*, types::*, utils::*, watch::*,
 };
 
+/// Why the application stopped without being closed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    /// The file should be read again, because it changed on disk or the user asked for it
+    Reload,
+    /// The file changed on disk while there were unsaved edits, they are kept until it is reloaded
+    Conflict,
+    /// The source was edited, the splice should be applied to it
+    Edit(Splice),
+    /// The last edit should be reverted
+    Undo,
+    /// The edited source should be written to the file
+    Write,
+}
+
+/// Cursor and selection written as key paths, so they can be found again after reloading the file
+#[derive(Debug, Clone, PartialEq, Eq, ImplHelper)]
+pub struct Bookmarks {
+    #[helper(get)]
+    cursor: KeyPath,
+
+    #[helper(get)]
+    selection: Vec<KeyPath>,
+}
+
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+const BREADCRUMB_SEPARATOR: &str = " › ";
+const LINES_NOTICE: &str =
+    "lines of a JSON Lines file can't be added or removed, only edited inside";
+
 #[derive(Debug)]
 pub struct App<'a> {
     running: bool,
@@ -22,11 +60,36 @@ pub struct App<'a> {
     path: Path,
     terminal_size: Size,
     preferences: Preferences,
+    prompt: Option<Prompt>,
+    search: Option<Search>,
+    /// Paths of the parts that could not be read, found the first time they are needed
+    errors: Option<Vec<Path>>,
+    last_click: Option<(Instant, Path)>,
+    /// Where the border between the tables was pressed and whether it has been dragged since
+    border_press: Option<(Position, bool)>,
+    /// Shown in the status line until the next status update
+    notice: Option<String>,
+    flake: Option<FlakeMode>,
+    diff: Option<Diff>,
+    /// Stops the application when the file changes, so it can be read again
+    watcher: Option<Watcher>,
+    /// Whether the source is the file itself, rather than a document made from it
+    editable: bool,
+    /// Whether the source has edits that are not written to the file yet
+    modified: bool,
+    /// Whether quitting was refused once because of the unsaved edits
+    close_refused: bool,
+    request: Option<Request>,
+    keymap: Keymap,
+    /// Keys of a sequence pressed so far, for example the first `g` of `gg`
+    pending_keys: Vec<KeyChord>,
 
     page: ViewModel<PageView<'a>, PageModel>,
     bottom_textline: ViewModel<TextLineView<'a>, TextLineModel>,
     status_textline: ViewModel<TextLineView<'a>, TextLineModel>,
     flpath_textline: ViewModel<TextLineView<'a>, TextLineModel>,
+    /// Shown over the page while it's open
+    help: Option<ViewModel<HelpView<'a>, HelpModel>>,
 }
 
 pub fn nodes_in_path_to_columns(
@@ -35,6 +98,8 @@ pub fn nodes_in_path_to_columns(
     node_index_offset: usize,
     selected_entries: &Vec<Rc<Entry>>,
     column_width: &u16,
+    flake: Option<&FlakeMode>,
+    diff: Option<&Diff>,
 ) -> anyhow::Result<Vec<Rc<RefCell<ColumnModel>>>> {
     if path.is_empty() {
         liab!("Path could not be empty");
@@ -84,6 +149,32 @@ pub fn nodes_in_path_to_columns(
                     .iter()
                     .map(|child| child.entry().clone())
                     .collect_vec();
+                let ancestors = match (flake, diff) {
+                    (None, None) => vec![],
+                    _ => arg_context!(entries_in_path(root, &path[..node_index_offset + i]))?,
+                };
+                let annotations = entries
+                    .iter()
+                    .map(|entry| {
+                        flake
+                            .and_then(|flake| flake.annotate(&ancestors, entry))
+                            .or_else(|| diff.and_then(|diff| diff.annotate(&ancestors, entry)))
+                            .or_else(|| entry.mistake().clone())
+                            .or_else(|| {
+                                entry
+                                    .comment()
+                                    .as_ref()
+                                    .map(|comment| format!("// {}", comment))
+                            })
+                    })
+                    .collect_vec();
+                let changes = match diff {
+                    Some(diff) => entries
+                        .iter()
+                        .map(|entry| diff.change(&ancestors, entry))
+                        .collect_vec(),
+                    None => vec![],
+                };
                 let constraint =
                     DirectionalConstraint::Horizontal(Constraint::Length(column_width.clone()));
 
@@ -92,6 +183,8 @@ pub fn nodes_in_path_to_columns(
                         .highlight_index_set(highlight_index)
                         .is_active_set(is_active)
                         .entries_set(entries)
+                        .annotations_set(annotations)
+                        .changes_set(changes)
                         .selected_entries_set(selected_entries.clone())
                         .constraint_set(constraint),
                 ))
@@ -102,17 +195,29 @@ pub fn nodes_in_path_to_columns(
     anyhow::Ok(columns)
 }
 
+/// Names of the keys along the path, array items are shown by their indices
+pub fn breadcrumb(root: &Node, path: &[Step]) -> anyhow::Result<String> {
+    let steps = arg_context!(entries_in_path(root, path))?
+        .into_iter()
+        .map(|entry| match entry.role() {
+            EntryRole::Item => entry.path().last().copied().unwrap_or_default().to_string(),
+            _ => entry.name().clone(),
+        })
+        .collect::<Vec<String>>();
+
+    anyhow::Ok(steps.join(BREADCRUMB_SEPARATOR))
+}
+
 impl<'a> App<'a> {
     pub fn new(
         terminal_size: Size,
-        file: &PathBuf,
-        source: &'a [u8],
+        file: &str,
+        mut root: Node<'a>,
         path: Box<[Step]>,
+        flake: Option<FlakeMode>,
+        diff: Option<Diff>,
+        theme: Rc<Theme>,
     ) -> anyhow::Result<Self> {
-        if source.is_empty() {
-            liab!("Provided file does not contain any data to show");
-        }
-
         let mut preferences = Preferences::default();
         arg_context!(preferences.apply_term_width(terminal_size.width))?;
 
@@ -122,7 +227,7 @@ impl<'a> App<'a> {
         let bottom_textline_constraint = DirectionalConstraint::Vertical(Constraint::Length(1));
         let status_textline_constraint = DirectionalConstraint::Horizontal(Constraint::Min(1));
         let flpath_textline_constraint = DirectionalConstraint::Horizontal(Constraint::Max(
-            u16::try_from(file.display().to_string().chars().count()).unwrap_or(u16::MAX), // FIXME
+            u16::try_from(file.chars().count()).unwrap_or(u16::MAX), // FIXME
         ));
 
         let [page_area, bottom_textline_area] = Layout::vertical(
@@ -153,13 +258,8 @@ impl<'a> App<'a> {
         .flex(Flex::SpaceBetween)
         .areas(bottom_textline_area);
 
-        let source_location = Location::new(0, arg_context!(source.len().checked_sub(1))?);
-        let mut root = arg_context!(Node::new(
-            source,
-            Some(source_location),
-            Rc::new(Entry::default()),
-            Paginator::new(page_area.height.into(), 0, None)
-        ))?;
+        root.paginator_mut()
+            .size_update(usize::from(page_area.height));
 
         let path = arg_context!(validate_path(&mut root, &path))?;
         if path.is_empty() {
@@ -175,8 +275,11 @@ impl<'a> App<'a> {
                             &path,
                             0,
                             &Vec::<Rc<Entry>>::default(),
-                            preferences.left_table_column_width()
+                            preferences.left_table_column_width(),
+                            flake.as_ref(),
+                            diff.as_ref()
                         ))?)
+                        .theme_set(theme.clone())
                         .constraint_set(DirectionalConstraint::Horizontal(Constraint::Min(
                             preferences.left_table_column_width().clone()
                         ))),
@@ -192,6 +295,7 @@ impl<'a> App<'a> {
                                 ))
                                 .clone(),
                         ))])
+                        .theme_set(theme.clone())
                         .constraint_set(DirectionalConstraint::Horizontal(Constraint::Max(0))),
                 )
                 .constraint_set(page_constraint)
@@ -202,7 +306,7 @@ impl<'a> App<'a> {
             TextLineModel::default()
                 .area_set(bottom_textline_area)
                 .hide_spans_set(vec![(format!(" "), Style::default())])
-                .style_set(Style::default().bg(Color::Rgb(80, 73, 69)))
+                .style_set(*theme.status_bar())
                 .alignment_set(Alignment::Center)
                 .constraint_set(bottom_textline_constraint),
         );
@@ -211,25 +315,21 @@ impl<'a> App<'a> {
             TextLineModel::default()
                 .area_set(status_textline_area)
                 .hide_spans_set(vec![(
-                    format!(
-                        "[{}:{}]: {}",
-                        arg_context!(path.len().checked_sub(1))?,
-                        arg_context!(path.last())?,
-                        arg_context!(node_by_path(&root, &path))?.entry().name()
-                    ),
+                    arg_context!(breadcrumb(&root, &path))?,
                     Style::default(),
                 )])
-                .style_set(Style::default().white())
+                .style_set(*theme.status_bar())
                 .alignment_set(Alignment::Left)
                 .transparent_set(true)
+                .clip_start_set(true)
                 .constraint_set(status_textline_constraint),
         );
 
         let flpath_textline = ViewModel::default().model_set(
             TextLineModel::default()
                 .area_set(flpath_textline_area)
-                .hide_spans_set(vec![(file.display().to_string(), Style::default())])
-                .style_set(Style::default().white())
+                .hide_spans_set(vec![(file.to_string(), Style::default())])
+                .style_set(*theme.status_bar())
                 .alignment_set(Alignment::Right)
                 .transparent_set(true)
                 .constraint_set(flpath_textline_constraint),
@@ -241,10 +341,26 @@ impl<'a> App<'a> {
             path,
             terminal_size,
             preferences,
+            prompt: None,
+            search: None,
+            errors: None,
+            last_click: None,
+            border_press: None,
+            notice: None,
+            flake,
+            diff,
+            watcher: None,
+            editable: false,
+            modified: false,
+            close_refused: false,
+            request: None,
+            keymap: Keymap::default(),
+            pending_keys: vec![],
             page,
             bottom_textline,
             status_textline,
             flpath_textline,
+            help: None,
         };
 
         anyhow::Ok(app)
@@ -410,7 +526,8 @@ impl<'a> App<'a> {
                     draw = true;
                 }
                 Event::Mouse(mouse_event) => {
-                    todo!("{:#?}", mouse_event);
+                    arg_context!(handle_mouse_events(mouse_event, self))?;
+                    draw = true;
                 }
             }
         }
@@ -418,12 +535,108 @@ impl<'a> App<'a> {
         anyhow::Ok(())
     }
 
-    pub fn tick(&self) {}
+    pub fn tick(&mut self) {
+        if self
+            .watcher
+            .as_mut()
+            .is_some_and(|watcher| watcher.changed())
+        {
+            self.stop(match self.modified {
+                true => Request::Conflict,
+                false => Request::Reload,
+            });
+        }
+    }
+
+    /// Stops the application, so the caller can handle the request and start it again
+    fn stop(&mut self, request: Request) {
+        self.request = Some(request);
+        self.running = false;
+    }
+
+    pub fn watch(&mut self, watcher: Watcher) {
+        self.watcher = Some(watcher);
+    }
+
+    pub fn enable_editing(&mut self) {
+        self.editable = true;
+    }
+
+    pub fn mark_modified(&mut self) {
+        self.modified = true;
+    }
+
+    /// Why the application was stopped, `None` if it was closed
+    pub fn request(&self) -> Option<&Request> {
+        self.request.as_ref()
+    }
+
+    /// Shows the message in the status line until the next status update
+    pub fn notify(&mut self, notice: String) -> anyhow::Result<()> {
+        self.notice = Some(notice);
+        arg_context!(self.update_status())
+    }
+
+    /// Key paths of the cursor and of the selected entries
+    pub fn bookmarks(&self) -> anyhow::Result<Bookmarks> {
+        let cursor = KeyPath::new(&arg_context!(entries_in_path(&self.root, &self.path))?);
+
+        let rght_table_column = arg_context!(self.page.model().rght_table().hide_columns().get(0))?;
+        let rght_table_column = arg_context!(rght_table_column.try_borrow())?;
+
+        let mut selection = vec![];
+        for entry in rght_table_column.entries().iter() {
+            let nodes = arg_context!(nodes_along_path(
+                self.root.source(),
+                *self.root.mode(),
+                entry.path()
+            ))?;
+            let entries = nodes
+                .iter()
+                .map(|node| node.entry().as_ref())
+                .collect::<Vec<&Entry>>();
+
+            selection.push(KeyPath::new(&entries));
+        }
+
+        anyhow::Ok(Bookmarks { cursor, selection })
+    }
+
+    /// Selects the entries that are still in the file, in the same order
+    pub fn restore_selection(&mut self, selection: &[KeyPath]) -> anyhow::Result<()> {
+        let source = self.root.source();
+        let mut entries = vec![];
+
+        // Selected entries are inserted at the beginning, so the last one goes first
+        for key_path in selection.iter().rev() {
+            if let Some(path) = key_path.resolve(source, *self.root.mode()) {
+                let nodes = arg_context!(nodes_along_path(source, *self.root.mode(), &path))?;
+                entries.push(arg_context!(nodes.last())?.entry().clone());
+            }
+        }
+
+        arg_context!(self.toggle_entries(&entries))
+    }
 
     pub fn quit(&mut self) {
         self.running = false;
     }
 
+    /// Quits, unless there are edits that are not written yet, then it has to be done once more
+    pub fn close(&mut self) -> anyhow::Result<()> {
+        if self.modified && !self.close_refused {
+            self.close_refused = true;
+
+            return self.notify(String::from(
+                "there are unsaved edits, press w to write them or q again to quit",
+            ));
+        }
+
+        self.quit();
+
+        anyhow::Ok(())
+    }
+
     pub fn inc_left_table_column_width(&mut self) -> anyhow::Result<()> {
         let is_left_table_visible = self.page.model().left_table().area().width != 0;
         let is_rght_table_visible = self.page.model().rght_table().area().width != 0;
@@ -546,6 +759,64 @@ impl<'a> App<'a> {
         anyhow::Ok(())
     }
 
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Action of the key together with the keys pressed before it, `None` while a sequence is not finished
+    pub fn key_action(&mut self, key_event: &KeyEvent) -> Option<Action> {
+        self.pending_keys.push(KeyChord::from(key_event));
+
+        match self.keymap.lookup(&self.pending_keys) {
+            KeyMatch::Action(action) => {
+                self.pending_keys.clear();
+                Some(action)
+            }
+            KeyMatch::Pending => None,
+            // A key that doesn't continue the sequence starts over
+            KeyMatch::None if self.pending_keys.len() > 1 => {
+                self.pending_keys.clear();
+                self.key_action(key_event)
+            }
+            KeyMatch::None => {
+                self.pending_keys.clear();
+                None
+            }
+        }
+    }
+
+    pub fn help(&self) -> Option<&HelpModel> {
+        self.help.as_ref().map(|help| help.model())
+    }
+
+    pub fn open_help(&mut self) {
+        let mut model = HelpModel::default().lines_set(
+            self.keymap
+                .help()
+                .into_iter()
+                .map(|(keys, description)| (keys, description.to_string()))
+                .collect(),
+        );
+        model.area_update(model.centered_area(*self.page.model().area()));
+
+        self.help = Some(ViewModel::default().model_set(model));
+    }
+
+    pub fn close_help(&mut self) {
+        self.help = None;
+    }
+
+    pub fn scroll_help(&mut self, delta: isize) -> anyhow::Result<()> {
+        if let Some(help) = &mut self.help {
+            raw_context!(help.with_model_mut(|model: &mut HelpModel| {
+                model.scroll(delta);
+                anyhow::Ok(())
+            }))?;
+        }
+
+        anyhow::Ok(())
+    }
+
     pub fn set_terminal_size(&mut self, terminal_size: Size) -> anyhow::Result<()> {
         self.terminal_size = terminal_size;
 
@@ -605,7 +876,9 @@ impl<'a> App<'a> {
             &self.path,
             0,
             &selected_entries,
-            self.preferences.left_table_column_width()
+            self.preferences.left_table_column_width(),
+            self.flake.as_ref(),
+            self.diff.as_ref()
         ))?;
 
         raw_context!(self.page.try_with_model_mut(|model| {
@@ -644,10 +917,47 @@ impl<'a> App<'a> {
             anyhow::Ok(())
         }))?;
 
+        if let Some(help) = &mut self.help {
+            raw_context!(help.with_model_mut(|model: &mut HelpModel| {
+                model.area_update(model.centered_area(page_area));
+                // Keeps the last page full after the area grows
+                model.scroll(0);
+                anyhow::Ok(())
+            }))?;
+        }
+
         anyhow::Ok(())
     }
 
+    /// Moves the cursor to the first or the last entry of its column
+    pub fn cursor_to_end(&mut self, last: bool) -> anyhow::Result<()> {
+        let mut path = self.path.clone();
+        let Some(step) = path.last_mut() else {
+            return anyhow::Ok(());
+        };
+
+        // The last step is found when the path is made, the lines of a JSON Lines file aren't all known before
+        *step = match last {
+            true => Step::MAX,
+            false => 0,
+        };
+
+        arg_context!(self.jump_to_path(&path))
+    }
+
     pub fn cursor_move(&mut self, cursor_direction: CursorDirection) -> anyhow::Result<()> {
+        if cursor_direction == CursorDirection::Right {
+            if let Some(target) = arg_context!(self.reference_target())? {
+                let query = PathQuery::Steps(vec![
+                    QueryStep::Key(String::from("nodes")),
+                    QueryStep::Key(target),
+                ]);
+                let path = arg_context!(query.resolve(self.root.source(), *self.root.mode()))?;
+
+                return self.jump_to_path(&path);
+            }
+        }
+
         let (new_path, columns_drain_index, node_index_offset, highlight_index) =
             arg_context!(self.update_data(&cursor_direction))?;
 
@@ -663,7 +973,9 @@ impl<'a> App<'a> {
             &self.path,
             node_index_offset,
             &selected_entries,
-            self.preferences.left_table_column_width()
+            self.preferences.left_table_column_width(),
+            self.flake.as_ref(),
+            self.diff.as_ref()
         ))?;
 
         raw_context!(self.page.try_with_model_mut(|model| {
@@ -677,18 +989,49 @@ impl<'a> App<'a> {
             anyhow::Ok(())
         }))?;
 
-        raw_context!(self.status_textline.with_model_mut(|model| {
-            model.hide_spans_update(vec![(
-                format!(
-                    "[{}:{}]: {}",
-                    arg_context!(self.path.len().checked_sub(1))?,
-                    arg_context!(self.path.last())?,
-                    arg_context!(node_by_path(&self.root, &self.path))?
-                        .entry()
-                        .name()
-                ),
+        arg_context!(self.update_status())?;
+
+        anyhow::Ok(())
+    }
+
+    fn status_spans(&self) -> anyhow::Result<Vec<(String, Style)>> {
+        if let Some(prompt) = &self.prompt {
+            let mut spans = vec![(
+                format!("{}{}", prompt.kind().symbol(), prompt.input()),
                 Style::default(),
-            )]);
+            )];
+
+            if let Some(search) = &self.search {
+                spans.push((format!(" ({})", search.status()), Style::default().dim()));
+            }
+
+            return anyhow::Ok(spans);
+        }
+
+        if let Some(notice) = &self.notice {
+            return anyhow::Ok(vec![(notice.clone(), Style::default().dim())]);
+        }
+
+        let mut spans = vec![(
+            arg_context!(breadcrumb(&self.root, &self.path))?,
+            Style::default(),
+        )];
+
+        if let Some(search) = &self.search {
+            spans.push((format!(" ({})", search.status()), Style::default().dim()));
+        }
+
+        anyhow::Ok(spans)
+    }
+
+    fn update_status(&mut self) -> anyhow::Result<()> {
+        let spans = arg_context!(self.status_spans())?;
+        // Notices are read from the beginning, while the end of the breadcrumb matters the most
+        let clip_start = self.notice.take().is_none();
+
+        raw_context!(self.status_textline.with_model_mut(|model| {
+            model.clip_start_update(clip_start);
+            model.hide_spans_update(spans);
 
             anyhow::Ok(())
         }))?;
@@ -696,103 +1039,528 @@ impl<'a> App<'a> {
         anyhow::Ok(())
     }
 
-    pub fn select_entry(&mut self) -> anyhow::Result<()> {
-        let node = arg_context!(node_by_path(&self.root, &self.path))?;
-        let hovered_entry = node.entry();
+    /// Rebuilds the tree along the given path and moves the cursor there.
+    /// Steps that do not exist are dropped by `validate_path`.
+    pub fn jump_to_path(&mut self, path: &[Step]) -> anyhow::Result<()> {
+        self.root.kill_children();
 
-        let [new_left_width, new_rght_width] =
-            arg_context!(self.preferences.apply_term_width(self.terminal_size.width))?;
+        let path = arg_context!(validate_path(&mut self.root, path))?;
+        if path.is_empty() {
+            liab!("Could not jump to an empty path");
+        }
+
+        self.path = path;
+
+        let selected_entries = {
+            let column = arg_context!(self.page.model().rght_table().hide_columns().first())?;
+            arg_context!(column.try_borrow())?.entries().clone()
+        };
+
+        let new_columns = arg_context!(nodes_in_path_to_columns(
+            &self.root,
+            &self.path,
+            0,
+            &selected_entries,
+            self.preferences.left_table_column_width(),
+            self.flake.as_ref(),
+            self.diff.as_ref()
+        ))?;
 
         raw_context!(self.page.try_with_model_mut(|model| {
-            if let Some(new_width) = new_left_width {
-                arg_context!(model.set_left_table_width(new_width))?;
+            arg_context!(model.left_table_mut().update(
+                &CursorDirection::Down,
+                Some(0),
+                None::<usize>,
+                &new_columns,
+            ))?;
+
+            anyhow::Ok(())
+        }))?;
+
+        arg_context!(self.update_status())?;
+
+        anyhow::Ok(())
+    }
+
+    /// Node of the flake.lock the entry under the cursor refers to
+    fn reference_target(&self) -> anyhow::Result<Option<String>> {
+        let Some(flake) = &self.flake else {
+            return anyhow::Ok(None);
+        };
+
+        let path = arg_context!(entries_in_path(&self.root, &self.path))?;
+
+        anyhow::Ok(flake.reference_target(&path))
+    }
+
+    /// Copies the JSON Pointer of the entry under the cursor to the clipboard
+    pub fn copy_pointer(&mut self) -> anyhow::Result<()> {
+        let pointer = json_pointer(arg_context!(entries_in_path(&self.root, &self.path))?);
+
+        arg_context!(copy_to_clipboard(&pointer))?;
+
+        self.notice = Some(format!("copied {}", pointer));
+        arg_context!(self.update_status())?;
+
+        anyhow::Ok(())
+    }
+
+    pub fn prompt(&self) -> Option<&Prompt> {
+        self.prompt.as_ref()
+    }
+
+    pub fn open_prompt(&mut self, kind: PromptKind) -> anyhow::Result<()> {
+        self.prompt = Some(Prompt::new(kind, self.path.clone()));
+
+        if kind == PromptKind::Search {
+            self.search = None;
+        }
+
+        arg_context!(self.update_status())
+    }
+
+    /// Whether the file can be edited, if not the reason is shown in the status line
+    fn check_editable(&mut self) -> anyhow::Result<bool> {
+        if !self.editable {
+            arg_context!(self.notify(String::from(
+                "only files can be edited, not stdin or the generated views",
+            )))?;
+        }
+
+        anyhow::Ok(self.editable)
+    }
+
+    /// Block holding the entry at the path and the index of the entry in it, values stand for their keys or items.
+    /// The root is not in any block, and neither are the lines of a JSON Lines file.
+    fn member_at(&self, path: &[Step]) -> anyhow::Result<Option<(Block, Step)>> {
+        let mut path = path.to_vec();
+
+        if arg_context!(node_by_path(&self.root, &path))?
+            .entry()
+            .role()
+            == &EntryRole::Value
+        {
+            path.pop();
+        }
+
+        let Some((index, parent_path)) = path.split_last() else {
+            return anyhow::Ok(None);
+        };
+
+        if parent_path.is_empty() && *self.root.mode().lines() {
+            return anyhow::Ok(None);
+        }
+
+        let parent = arg_context!(node_by_path(&self.root, parent_path))?
+            .entry()
+            .clone();
+        let location = arg_context!(parent.value_location().as_ref())?;
+        let block = arg_context!(Block::new(self.root.source(), *location))?;
+
+        anyhow::Ok(Some((block, *index)))
+    }
+
+    /// Object or array under the cursor
+    fn block_at(&self, path: &[Step]) -> anyhow::Result<Option<Block>> {
+        let entry = arg_context!(node_by_path(&self.root, path))?
+            .entry()
+            .clone();
+
+        match (entry.kind(), entry.value_location()) {
+            (ValueKind::Object | ValueKind::Array, Some(location)) => anyhow::Ok(Some(
+                arg_context!(Block::new(self.root.source(), *location))?,
+            )),
+            _ => anyhow::Ok(None),
+        }
+    }
+
+    /// Stops with the splice if the edited source can still be read, otherwise the reason is shown on the next status update.
+    /// Only `structural` edits read the whole source again, a scalar checked by `validate_scalar` can't break it.
+    fn request_edit(&mut self, splice: anyhow::Result<Splice>, structural: bool) {
+        let splice = splice.and_then(|splice| {
+            if structural {
+                arg_context!(validate_source(
+                    &splice.apply(self.root.source()),
+                    self.root.source(),
+                    *self.root.mode()
+                ))?;
             }
-            if let Some(new_width) = new_rght_width {
-                arg_context!(model.set_rght_table_width(new_width))?;
+
+            anyhow::Ok(splice)
+        });
+
+        match splice {
+            Ok(splice) if splice.changes_nothing() => {}
+            Ok(splice) => self.stop(Request::Edit(splice)),
+            Err(err) => self.notice = Some(error_message(&err)),
+        }
+    }
+
+    /// Opens the prompt with the value under the cursor, as it is written in the source
+    pub fn open_editor(&mut self) -> anyhow::Result<()> {
+        if !arg_context!(self.check_editable())? {
+            return anyhow::Ok(());
+        }
+
+        let entry = arg_context!(node_by_path(&self.root, &self.path))?
+            .entry()
+            .clone();
+
+        let location = match (entry.kind(), entry.value_location()) {
+            (ValueKind::Object | ValueKind::Array, _) | (_, None) => {
+                return self.notify(String::from("only scalar values can be edited"));
             }
+            (_, Some(location)) => location,
+        };
 
-            let before = {
-                let rght_table_column = arg_context!(model.rght_table().hide_columns().get(0))?;
-                let rght_table_column = arg_context!(rght_table_column.try_borrow())?;
+        let input = String::from_utf8_lossy(self.root.source().slice(location)).to_string();
+        self.prompt = Some(Prompt::new(PromptKind::Edit, self.path.clone()).input_set(input));
 
-                rght_table_column.entries().is_empty()
-            };
+        arg_context!(self.update_status())
+    }
 
-            let entry_in_rght_table = {
-                let rght_table_column =
-                    arg_context!(model.rght_table_mut().hide_columns_mut().get_mut(0))?;
-                let rght_table_column = arg_context!(rght_table_column.try_borrow_mut())?;
+    /// Opens the prompt with the key under the cursor, as it is written in the source
+    pub fn open_renamer(&mut self) -> anyhow::Result<()> {
+        if !arg_context!(self.check_editable())? {
+            return anyhow::Ok(());
+        }
 
-                rght_table_column.entries().contains(hovered_entry)
-            };
+        match arg_context!(self.member_at(&self.path))? {
+            Some((block, _)) if block.is_object() => {}
+            _ => return self.notify(String::from("only object keys can be renamed")),
+        }
 
-            raw_context!(model
-                .left_table_mut()
-                .hide_columns_mut()
-                .iter_mut()
-                .try_for_each(|column| {
-                    if entry_in_rght_table {
-                        arg_context!(column.try_borrow_mut())?
-                            .selected_entries_mut()
-                            .retain(|entry| entry != hovered_entry);
-                    } else {
-                        arg_context!(column.try_borrow_mut())?
-                            .selected_entries_mut()
-                            .insert(0, hovered_entry.clone());
-                    }
+        let input = arg_context!(node_by_path(&self.root, &self.path))?
+            .entry()
+            .name()
+            .clone();
+        self.prompt = Some(Prompt::new(PromptKind::Rename, self.path.clone()).input_set(input));
 
-                    anyhow::Ok(())
-                }))?;
+        arg_context!(self.update_status())
+    }
 
-            raw_context!(model
-                .rght_table_mut()
-                .hide_columns_mut()
-                .iter_mut()
-                .try_for_each(|column| {
-                    if entry_in_rght_table {
-                        arg_context!(column.try_borrow_mut())?
-                            .entries_mut()
-                            .retain(|entry| entry != hovered_entry);
-                        arg_context!(column.try_borrow_mut())?
-                            .selected_entries_mut()
-                            .retain(|entry| entry != hovered_entry);
-                    } else {
-                        arg_context!(column.try_borrow_mut())?
-                            .entries_mut()
-                            .insert(0, hovered_entry.clone());
-                        arg_context!(column.try_borrow_mut())?
-                            .selected_entries_mut()
-                            .insert(0, hovered_entry.clone());
-                    }
+    /// Opens the prompt for a new member, after the one under the cursor or as the first one of the object or array under it
+    pub fn open_inserter(&mut self, kind: PromptKind) -> anyhow::Result<()> {
+        if !arg_context!(self.check_editable())? {
+            return anyhow::Ok(());
+        }
 
-                    anyhow::Ok(())
-                }))?;
+        if kind == PromptKind::InsertFirst && arg_context!(self.block_at(&self.path))?.is_none() {
+            return self.notify(String::from(
+                "only objects and arrays can have members inserted",
+            ));
+        }
 
-            let after = {
-                let rght_table_column = arg_context!(model.rght_table().hide_columns().get(0))?;
-                let rght_table_column = arg_context!(rght_table_column.try_borrow())?;
+        if kind == PromptKind::InsertAfter
+            && *self.root.mode().lines()
+            && arg_context!(self.member_at(&self.path))?.is_none()
+        {
+            return self.notify(String::from(LINES_NOTICE));
+        }
 
-                rght_table_column.entries().is_empty()
-            };
+        self.prompt = Some(Prompt::new(kind, self.path.clone()));
 
-            if after != before {
-                arg_context!(model.set_rght_table_width(if after {
-                    0
-                } else {
-                    self.preferences.rght_table_column_width().clone()
-                }))?;
+        arg_context!(self.update_status())
+    }
+
+    pub fn delete_entry(&mut self) -> anyhow::Result<()> {
+        if !arg_context!(self.check_editable())? {
+            return anyhow::Ok(());
+        }
+
+        let Some((block, index)) = arg_context!(self.member_at(&self.path))? else {
+            return self.notify(String::from(match *self.root.mode().lines() {
+                true => LINES_NOTICE,
+                false => "the whole document can't be deleted",
+            }));
+        };
+
+        self.request_edit(block.delete(self.root.source(), index), true);
+
+        arg_context!(self.update_status())
+    }
+
+    pub fn duplicate_entry(&mut self) -> anyhow::Result<()> {
+        if !arg_context!(self.check_editable())? {
+            return anyhow::Ok(());
+        }
+
+        let Some((block, index)) = arg_context!(self.member_at(&self.path))? else {
+            return self.notify(String::from(match *self.root.mode().lines() {
+                true => LINES_NOTICE,
+                false => "the whole document can't be duplicated",
+            }));
+        };
+
+        self.request_edit(block.duplicate(self.root.source(), index), true);
+
+        arg_context!(self.update_status())
+    }
+
+    /// Splice that replaces the value at the path with the text
+    fn edit_value(&self, path: &[Step], text: &str) -> anyhow::Result<Splice> {
+        let entry = arg_context!(node_by_path(&self.root, path))?
+            .entry()
+            .clone();
+        let location = arg_context!(entry.value_location().as_ref())?;
+
+        // Broken parts can be rewritten as anything, the edit is refused only if it breaks more
+        if entry.kind() != &ValueKind::Error {
+            arg_context!(validate_scalar(text))?;
+        }
+
+        anyhow::Ok(Splice::new(
+            self.root.source(),
+            location,
+            text.trim().as_bytes().to_vec(),
+        ))
+    }
+
+    /// Splice that replaces the key at the path with the text
+    fn rename_key(&self, path: &[Step], text: &str) -> anyhow::Result<Splice> {
+        let (block, index) = arg_context!(arg_context!(self.member_at(path))?)?;
+
+        block.rename(self.root.source(), index, text)
+    }
+
+    /// Splice that inserts the text as a new member, `kind` tells where
+    fn insert_member(&self, kind: PromptKind, path: &[Step], text: &str) -> anyhow::Result<Splice> {
+        if kind == PromptKind::InsertFirst {
+            let block = arg_context!(arg_context!(self.block_at(path))?)?;
+            arg_context!(validate_member(text, block.is_object()))?;
+
+            return block.insert_first(self.root.source(), text);
+        }
+
+        let (block, index) = arg_context!(arg_context!(self.member_at(path))?)?;
+        arg_context!(validate_member(text, block.is_object()))?;
+
+        block.insert_after(self.root.source(), index, text)
+    }
+
+    pub fn undo(&mut self) -> anyhow::Result<()> {
+        if arg_context!(self.check_editable())? {
+            self.stop(Request::Undo);
+        }
+
+        anyhow::Ok(())
+    }
+
+    pub fn reload(&mut self) -> anyhow::Result<()> {
+        if !self.editable {
+            return self.notify(String::from(
+                "only files can be reloaded, not stdin or the generated views",
+            ));
+        }
+
+        self.stop(Request::Reload);
+
+        anyhow::Ok(())
+    }
+
+    pub fn write(&mut self) -> anyhow::Result<()> {
+        if !arg_context!(self.check_editable())? {
+            return anyhow::Ok(());
+        }
+
+        if !self.modified {
+            return self.notify(String::from("there are no edits to write"));
+        }
+
+        self.stop(Request::Write);
+
+        anyhow::Ok(())
+    }
+
+    pub fn prompt_input(&mut self, character: char) -> anyhow::Result<()> {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input_mut().push(character);
+        }
+
+        arg_context!(self.prompt_changed())
+    }
+
+    pub fn prompt_backspace(&mut self) -> anyhow::Result<()> {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input_mut().pop();
+        }
+
+        arg_context!(self.prompt_changed())
+    }
+
+    pub fn prompt_clear(&mut self) -> anyhow::Result<()> {
+        if let Some(prompt) = &mut self.prompt {
+            prompt.input_mut().clear();
+        }
+
+        arg_context!(self.prompt_changed())
+    }
+
+    fn prompt_changed(&mut self) -> anyhow::Result<()> {
+        arg_context!(self.update_status())
+    }
+
+    pub fn prompt_submit(&mut self) -> anyhow::Result<()> {
+        if let Some(prompt) = self.prompt.take() {
+            match prompt.kind() {
+                // The whole source is read for the matches, so it is done once, not on every key
+                PromptKind::Search if prompt.input().is_empty() => {
+                    self.search = None;
+                }
+                PromptKind::Search => {
+                    let origin = prompt.origin().clone();
+                    let search = arg_context!(Search::new(
+                        self.root.source(),
+                        *self.root.mode(),
+                        prompt.input().clone(),
+                        &origin
+                    ))?;
+                    let target = search.current_path().cloned().unwrap_or(origin);
+
+                    self.search = Some(search);
+                    arg_context!(self.jump_to_path(&target))?;
+                }
+                PromptKind::Jump => {
+                    let path = PathQuery::parse(prompt.input())
+                        .and_then(|query| query.resolve(self.root.source(), *self.root.mode()));
+
+                    match path {
+                        Ok(path) => {
+                            let path = cursor_path(path);
+                            arg_context!(self.jump_to_path(&path))?
+                        }
+                        // Typos in the query are not fatal, the reason is shown in the status line
+                        Err(err) => self.notice = Some(error_message(&err)),
+                    }
+                }
+                PromptKind::Edit => {
+                    let broken = node_by_path(&self.root, prompt.origin())
+                        .is_ok_and(|node| node.entry().kind() == &ValueKind::Error);
+
+                    self.request_edit(self.edit_value(prompt.origin(), prompt.input()), broken);
+                }
+                PromptKind::Rename => {
+                    self.request_edit(self.rename_key(prompt.origin(), prompt.input()), false);
+                }
+                PromptKind::InsertAfter | PromptKind::InsertFirst => {
+                    let splice =
+                        self.insert_member(*prompt.kind(), prompt.origin(), prompt.input());
+                    self.request_edit(splice, true);
+                }
             }
+        }
 
-            anyhow::Ok(())
-        }))?;
+        arg_context!(self.update_status())
+    }
+
+    pub fn prompt_cancel(&mut self) -> anyhow::Result<()> {
+        if let Some(prompt) = self.prompt.take() {
+            match prompt.kind() {
+                PromptKind::Search => {
+                    self.search = None;
+                    arg_context!(self.jump_to_path(prompt.origin()))?;
+                }
+                PromptKind::Jump
+                | PromptKind::Edit
+                | PromptKind::Rename
+                | PromptKind::InsertAfter
+                | PromptKind::InsertFirst => {}
+            }
+        }
+
+        arg_context!(self.update_status())
+    }
+
+    pub fn search_next(&mut self) -> anyhow::Result<()> {
+        let target = self
+            .search
+            .as_mut()
+            .and_then(|search| search.next_match().cloned());
+
+        if let Some(target) = target {
+            arg_context!(self.jump_to_path(&target))?;
+        }
 
         anyhow::Ok(())
     }
 
+    pub fn search_prev(&mut self) -> anyhow::Result<()> {
+        let target = self
+            .search
+            .as_mut()
+            .and_then(|search| search.prev_match().cloned());
+
+        if let Some(target) = target {
+            arg_context!(self.jump_to_path(&target))?;
+        }
+
+        anyhow::Ok(())
+    }
+
+    /// Moves the cursor to the next part that could not be read, after the last one it goes back to the first
+    pub fn next_error(&mut self) -> anyhow::Result<()> {
+        if self.errors.is_none() {
+            self.errors = Some(arg_context!(find_errors(
+                self.root.source(),
+                *self.root.mode()
+            ))?);
+        }
+
+        let errors = self.errors.as_deref().unwrap_or_default();
+        let Some(index) = errors
+            .iter()
+            .position(|path| path.as_slice() > self.path.as_slice())
+            .or((!errors.is_empty()).then_some(0))
+        else {
+            return arg_context!(self.notify(String::from("there are no errors")));
+        };
+
+        let count = errors.len();
+        let target = errors[index].clone();
+        arg_context!(self.jump_to_path(&target))?;
+
+        let node = arg_context!(node_by_path(&self.root, &self.path))?;
+        self.notice = Some(format!(
+            "error {} of {}: {}",
+            index + 1,
+            count,
+            node.entry().name()
+        ));
+
+        arg_context!(self.update_status())
+    }
+
+    pub fn select_entry(&mut self) -> anyhow::Result<()> {
+        let node = arg_context!(node_by_path(&self.root, &self.path))?;
+        let path = arg_context!(entries_in_path(&self.root, &self.path))?;
+
+        // Groups of the duplicates report stand for the inputs in them
+        let entries = match &self.flake {
+            Some(flake) if flake.selects_children(&path) => node
+                .children()
+                .iter()
+                .map(|child| child.entry().clone())
+                .collect::<Vec<Rc<Entry>>>(),
+            _ => vec![node.entry().clone()],
+        };
+
+        arg_context!(self.toggle_entries(&entries))
+    }
+
     pub fn select_column(&mut self) -> anyhow::Result<()> {
         let cut = arg_context!(self.path.len().checked_sub(1))?;
         let hovered_column = node_by_path(&self.root, &self.path[0..cut])?;
+        let entries = hovered_column
+            .children()
+            .iter()
+            .map(|child| child.entry().clone())
+            .collect::<Vec<Rc<Entry>>>();
+
+        arg_context!(self.toggle_entries(&entries))
+    }
 
+    /// Selects the entries, or deselects them if all of them are already selected
+    fn toggle_entries(&mut self, entries: &[Rc<Entry>]) -> anyhow::Result<()> {
         let [new_left_width, new_rght_width] =
             arg_context!(self.preferences.apply_term_width(self.terminal_size.width))?;
 
@@ -814,9 +1582,7 @@ impl<'a> App<'a> {
             let mut all_entries_already_selected = true;
 
             // First loop (inserting only)
-            for child in hovered_column.children().iter() {
-                let hovered_entry = child.entry();
-
+            for hovered_entry in entries.iter() {
                 let entry_in_rght_table = {
                     let rght_table_column =
                         arg_context!(model.rght_table_mut().hide_columns_mut().get_mut(0))?;
@@ -863,9 +1629,7 @@ impl<'a> App<'a> {
 
             // Second loop (deletes all entries if all of them are already selected)
             if all_entries_already_selected {
-                for child in hovered_column.children().iter() {
-                    let hovered_entry = child.entry();
-
+                for hovered_entry in entries.iter() {
                     let entry_in_rght_table = {
                         let rght_table_column =
                             arg_context!(model.rght_table_mut().hide_columns_mut().get_mut(0))?;
@@ -928,15 +1692,170 @@ impl<'a> App<'a> {
         anyhow::Ok(())
     }
 
-    pub fn print(&self) -> anyhow::Result<()> {
+    pub fn print(&self, output: &Output) -> anyhow::Result<()> {
         let rght_table_column = arg_context!(self.page.model().rght_table().hide_columns().get(0))?;
+        let rght_table_column = arg_context!(rght_table_column.try_borrow())?;
+
+        arg_context!(output.write(
+            &mut stdout().lock(),
+            self.root.source(),
+            *self.root.mode(),
+            rght_table_column.entries()
+        ))?;
+
+        anyhow::Ok(())
+    }
+
+    fn entry_at(&self, position: Position) -> anyhow::Result<Option<Rc<Entry>>> {
+        for table in [
+            self.page.model().left_table(),
+            self.page.model().rght_table(),
+        ] {
+            if let Some(column) = arg_context!(table.column_at(position))? {
+                let column = arg_context!(column.try_borrow())?;
+                return anyhow::Ok(column.entry_at(position).cloned());
+            }
+        }
+
+        anyhow::Ok(None)
+    }
+
+    fn is_on_border(&self, position: Position) -> bool {
+        let rght_table_area = *self.page.model().rght_table().area();
+
+        rght_table_area.width != 0
+            && (position.x == rght_table_area.x || position.x + 1 == rght_table_area.x)
+            && position.y >= rght_table_area.y
+            && position.y < rght_table_area.bottom()
+    }
+
+    fn click(&mut self, position: Position) -> anyhow::Result<()> {
+        let Some(entry) = arg_context!(self.entry_at(position))? else {
+            self.last_click = None;
+            return anyhow::Ok(());
+        };
+
+        let is_double_click = self.last_click.as_ref().is_some_and(|(instant, path)| {
+            instant.elapsed() <= DOUBLE_CLICK_INTERVAL && path == entry.path()
+        });
+
+        arg_context!(self.jump_to_path(entry.path()))?;
+
+        if is_double_click {
+            self.last_click = None;
+            arg_context!(self.select_entry())?;
+        } else {
+            self.last_click = Some((Instant::now(), entry.path().clone()));
+        }
+
+        anyhow::Ok(())
+    }
+
+    pub fn mouse_down(&mut self, position: Position) -> anyhow::Result<()> {
+        if self.is_on_border(position) {
+            // It is still a click if the mouse is released without dragging
+            self.border_press = Some((position, false));
+            return anyhow::Ok(());
+        }
+
+        arg_context!(self.click(position))
+    }
+
+    pub fn mouse_drag(&mut self, position: Position) -> anyhow::Result<()> {
+        if let Some((_, dragged)) = &mut self.border_press {
+            *dragged = true;
+
+            let width = self.page.model().area().right().saturating_sub(position.x);
+            arg_context!(self.resize_rght_table(width))?;
+        }
+
+        anyhow::Ok(())
+    }
+
+    pub fn mouse_up(&mut self) -> anyhow::Result<()> {
+        if let Some((position, false)) = self.border_press.take() {
+            arg_context!(self.click(position))?;
+        }
+
+        anyhow::Ok(())
+    }
+
+    /// Shows the previous or the next page of the hovered column
+    pub fn mouse_scroll(
+        &mut self,
+        position: Position,
+        cursor_direction: CursorDirection,
+    ) -> anyhow::Result<()> {
+        let Some(column) = arg_context!(self.page.model().left_table().column_at(position))? else {
+            return anyhow::Ok(());
+        };
 
-        arg_context!(rght_table_column.try_borrow())?
+        let Some(first_path) = arg_context!(column.try_borrow())?
             .entries()
-            .iter()
-            .for_each(|entry| {
-                println!("{}", entry.name());
-            });
+            .first()
+            .map(|entry| entry.path().clone())
+        else {
+            return anyhow::Ok(());
+        };
+
+        let Some((first_step, parent_path)) = first_path.split_last() else {
+            return anyhow::Ok(());
+        };
+
+        let parent = arg_context!(node_by_path(&self.root, parent_path))?;
+        let page_size = *parent.paginator().size();
+        let total = parent.paginator().total().unwrap_or(0);
+
+        let target = match cursor_direction {
+            CursorDirection::Down => first_step
+                .checked_add(page_size)
+                .filter(|target| *target < total),
+            CursorDirection::Up => (*first_step > 0).then(|| first_step.saturating_sub(page_size)),
+            _ => None,
+        };
+
+        if let Some(target) = target {
+            let mut path = parent_path.to_vec();
+            path.push(target);
+
+            arg_context!(self.jump_to_path(&path))?;
+        }
+
+        anyhow::Ok(())
+    }
+
+    /// Sets the width of the right table, shrinking the left one if there is not enough space
+    pub fn resize_rght_table(&mut self, width: u16) -> anyhow::Result<()> {
+        let term_width = self.terminal_size.width;
+
+        let max_width = term_width.saturating_sub(MIN_LEFT_TABLE_COLUMN_WIDTH);
+        if max_width < MIN_RGHT_TABLE_COLUMN_WIDTH {
+            return anyhow::Ok(());
+        }
+
+        let rght_width = width.clamp(MIN_RGHT_TABLE_COLUMN_WIDTH, max_width);
+        arg_context!(self.preferences.rght_table_column_width_update(rght_width))?;
+
+        let left_width = *self.preferences.left_table_column_width();
+        let new_left_width = if left_width + rght_width > term_width {
+            Some(term_width - rght_width)
+        } else {
+            None
+        };
+
+        if let Some(new_width) = new_left_width {
+            arg_context!(self.preferences.left_table_column_width_update(new_width))?;
+        }
+
+        raw_context!(self.page.try_with_model_mut(|model| {
+            if let Some(new_width) = new_left_width {
+                arg_context!(model.set_left_table_width(new_width))?;
+            }
+
+            arg_context!(model.set_rght_table_width(rght_width))?;
+
+            anyhow::Ok(())
+        }))?;
 
         anyhow::Ok(())
     }
@@ -981,5 +1900,9 @@ impl<'a> Render for App<'a> {
         self.bottom_textline.render(frame);
         self.status_textline.render(frame);
         self.flpath_textline.render(frame);
+
+        if let Some(help) = &mut self.help {
+            help.render(frame);
+        }
     }
 }
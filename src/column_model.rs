@@ -1,8 +1,8 @@
 use impl_helper::ImplHelper;
-use ratatui::layout::Rect;
+use ratatui::layout::{Position, Rect};
 use std::rc::Rc;
 
-use crate::{directional_constraint::DirectionalConstraint, types::Entry};
+use crate::{diff::Change, directional_constraint::DirectionalConstraint, types::Entry};
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, ImplHelper)]
 pub struct ColumnModel {
@@ -18,6 +18,14 @@ pub struct ColumnModel {
     #[helper(all)]
     entries: Vec<Rc<Entry>>,
 
+    /// Notes shown next to the entries, one for each entry
+    #[helper(all)]
+    annotations: Vec<Option<String>>,
+
+    /// Differences between the compared files, one for each entry
+    #[helper(all)]
+    changes: Vec<Option<Change>>,
+
     #[helper(all)]
     selected_entries: Vec<Rc<Entry>>,
 
@@ -27,3 +35,15 @@ pub struct ColumnModel {
     #[helper(all)]
     constraint: DirectionalConstraint,
 }
+
+impl ColumnModel {
+    /// Every column shows a single page, so the list is never scrolled and rows map to entries directly
+    pub fn entry_at(&self, position: Position) -> Option<&Rc<Entry>> {
+        if !self.area.contains(position) {
+            return None;
+        }
+
+        self.entries
+            .get(usize::from(position.y.saturating_sub(self.area.y)))
+    }
+}
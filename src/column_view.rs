@@ -1,13 +1,32 @@
 use itertools::Itertools;
 use ratatui::{
     layout::Rect,
-    style::{Color, Style, Stylize},
+    style::{Style, Stylize},
     text::{Line, Span},
     widgets::{Clear, List, ListItem, ListState},
     Frame,
 };
 
-use crate::{column_model::ColumnModel, render::Render, utils::clip_string};
+use crate::{
+    column_model::ColumnModel,
+    render::Render,
+    theme::Theme,
+    types::{Entry, EntryRole, ValueKind},
+    utils::{clip_string, printable},
+};
+
+fn entry_style(entry: &Entry, theme: &Theme) -> Style {
+    if entry.mistake().is_some() {
+        return *theme.error();
+    }
+
+    match (entry.role(), entry.kind()) {
+        // Broken members are marked in the column of the keys as well
+        (EntryRole::Key, ValueKind::Error) => *theme.error(),
+        (EntryRole::Key, _) => Style::default(),
+        (_, kind) => theme.value_style(kind),
+    }
+}
 
 #[derive(Debug, Clone, Default)]
 pub struct ColumnView<'a> {
@@ -17,36 +36,49 @@ pub struct ColumnView<'a> {
     transparent: bool,
 }
 
-impl<'a> From<&ColumnModel> for ColumnView<'a> {
-    fn from(model: &ColumnModel) -> Self {
+impl<'a> ColumnView<'a> {
+    pub fn new(model: &ColumnModel, theme: &Theme) -> Self {
         let area = model.area().clone();
 
         let list = List::new(
             model
                 .entries()
                 .iter()
-                .map(|entry| {
+                .enumerate()
+                .map(|(i, entry)| {
                     let style;
 
                     if model.selected_entries().contains(entry) {
-                        style = Style::new().on_red();
+                        style = *theme.selected();
                     } else {
                         style = Style::default();
                     }
 
-                    ListItem::new(Line::from(vec![Span::from(clip_string(
-                        entry.name().clone(),
-                        "…",
-                        area.width.into(),
-                    ))]))
-                    .style(style)
+                    let name = clip_string(printable(entry.name()), "…", area.width.into());
+                    let rest_width =
+                        usize::from(area.width).saturating_sub(name.chars().count() + 1);
+
+                    let span = match model.changes().get(i) {
+                        Some(Some(change)) => Span::styled(name, theme.change_style(change)),
+                        _ => Span::styled(name, entry_style(entry, theme)),
+                    };
+
+                    let mut spans = vec![span];
+                    if let Some(Some(annotation)) = model.annotations().get(i) {
+                        spans.push(Span::from(" "));
+                        spans.push(
+                            Span::from(clip_string(printable(annotation), "…", rest_width)).dim(),
+                        );
+                    }
+
+                    ListItem::new(Line::from(spans)).style(style)
                 })
                 .collect_vec(),
         )
         .highlight_style(if *model.is_active() {
-            Style::default().bg(Color::Rgb(214, 94, 14))
+            *theme.active()
         } else {
-            Style::default().bg(Color::Rgb(80, 73, 69))
+            *theme.inactive()
         });
 
         let state = ListState::default().with_selected(model.highlight_index().clone());
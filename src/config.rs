@@ -0,0 +1,491 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+    str::CharIndices,
+};
+use wrap_context::{arg_context, liab};
+
+use crate::{keymap::Keymap, theme::Theme, utils::error_message};
+
+/// Value of a setting, only the parts of TOML the config file needs are supported
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigValue {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+}
+
+impl ConfigValue {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConfigValue::String(_) => "a string",
+            ConfigValue::Integer(_) => "an integer",
+            ConfigValue::Boolean(_) => "a boolean",
+        }
+    }
+}
+
+/// `key = value` line of the config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting {
+    /// Name of the `[table]` the setting is in, empty at the top of the file
+    pub table: String,
+    pub key: String,
+    pub value: ConfigValue,
+    /// Line number, starting at 1
+    pub line: usize,
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '_' | '-'))
+}
+
+/// Character of a `\u` or `\U` escape sequence, `digits` hex digits are read after the letter
+fn parse_code_point(
+    characters: &mut CharIndices,
+    letter: char,
+    digits: usize,
+) -> anyhow::Result<char> {
+    let mut hex = String::default();
+
+    while hex.len() < digits {
+        match characters.clone().next() {
+            Some((_, digit)) if digit.is_ascii_hexdigit() => {
+                hex.push(digit);
+                characters.next();
+            }
+            _ => liab!("\\{}{} needs {} hex digits", letter, hex, digits),
+        }
+    }
+
+    match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+        Some(character) => anyhow::Ok(character),
+        None => liab!("\\{}{} is not a valid character", letter, hex),
+    }
+}
+
+/// Reads a quoted string from the start of the text, returns it with the rest of the text
+fn parse_string(text: &str) -> anyhow::Result<(String, &str)> {
+    let mut characters = text.char_indices();
+
+    let quote = match characters.next() {
+        Some((_, quote @ ('"' | '\''))) => quote,
+        _ => liab!("expected a quoted string"),
+    };
+
+    let mut string = String::default();
+
+    while let Some((i, character)) = characters.next() {
+        match character {
+            _ if character == quote => return anyhow::Ok((string, &text[i + 1..])),
+            // Literal strings in single quotes have no escapes
+            '\\' if quote == '"' => {
+                let escaped = match characters.next() {
+                    Some((_, '"')) => '"',
+                    Some((_, '\\')) => '\\',
+                    Some((_, 'b')) => '\u{8}',
+                    Some((_, 'f')) => '\u{c}',
+                    Some((_, 'n')) => '\n',
+                    Some((_, 't')) => '\t',
+                    Some((_, 'r')) => '\r',
+                    Some((_, 'u')) => arg_context!(parse_code_point(&mut characters, 'u', 4))?,
+                    Some((_, 'U')) => arg_context!(parse_code_point(&mut characters, 'U', 8))?,
+                    Some((_, other)) => liab!("unknown escape sequence \\{}", other),
+                    None => break,
+                };
+
+                string.push(escaped);
+            }
+            _ => string.push(character),
+        }
+    }
+
+    liab!("the string is not closed with {}", quote)
+}
+
+/// Cuts the comment off, `#` inside quoted strings doesn't start one
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (i, character) in line.char_indices() {
+        match (quote, character) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), _) if character == open && !escaped => quote = None,
+            (None, '"' | '\'') => quote = Some(character),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+
+        escaped = false;
+    }
+
+    line
+}
+
+/// Reads a bare or a quoted key from the start of the text, returns it with the rest of the text
+fn parse_key(text: &str) -> anyhow::Result<(String, &str)> {
+    let text = text.trim_start();
+
+    if text.starts_with(['"', '\'']) {
+        return parse_string(text);
+    }
+
+    let end = text
+        .find(|character: char| character.is_whitespace() || matches!(character, '=' | ']'))
+        .unwrap_or(text.len());
+    let (key, rest) = text.split_at(end);
+
+    if !is_bare_key(key) {
+        liab!(
+            "{:?} is not a valid key, put it in quotes if it has characters other than letters, digits, _ and -",
+            key
+        );
+    }
+
+    anyhow::Ok((key.to_string(), rest))
+}
+
+fn parse_value(text: &str) -> anyhow::Result<ConfigValue> {
+    let text = text.trim();
+
+    if text.starts_with(['"', '\'']) {
+        let (value, rest) = arg_context!(parse_string(text))?;
+
+        if !rest.trim().is_empty() {
+            liab!("unexpected {:?} after the value", rest.trim());
+        }
+
+        return anyhow::Ok(ConfigValue::String(value));
+    }
+
+    match text {
+        "true" => return anyhow::Ok(ConfigValue::Boolean(true)),
+        "false" => return anyhow::Ok(ConfigValue::Boolean(false)),
+        "" => liab!("the value is missing"),
+        _ => {}
+    }
+
+    match text.replace('_', "").parse::<i64>() {
+        Ok(integer) => anyhow::Ok(ConfigValue::Integer(integer)),
+        Err(_) => liab!(
+            "{:?} is not a supported value, use a quoted string, an integer or a boolean",
+            text
+        ),
+    }
+}
+
+/// Settings of the config file, in the order they are written
+fn parse_settings(text: &str) -> anyhow::Result<Vec<Setting>> {
+    let mut settings = Vec::<Setting>::default();
+    let mut table = String::default();
+
+    for (i, line) in text.lines().enumerate() {
+        let line_number = i + 1;
+        let line = strip_comment(line).trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            table = match parse_key(name) {
+                Ok((name, rest)) if rest.trim() == "]" => name,
+                Ok(_) => liab!("line {}: expected `[table]`", line_number),
+                Err(err) => liab!("line {}: {}", line_number, error_message(&err)),
+            };
+
+            continue;
+        }
+
+        let setting = parse_key(line).and_then(|(key, rest)| {
+            let Some(value) = rest.trim_start().strip_prefix('=') else {
+                liab!("expected `key = value` or `[table]`");
+            };
+
+            let value = arg_context!(parse_value(value))?;
+
+            anyhow::Ok(Setting {
+                table: table.clone(),
+                key,
+                value,
+                line: line_number,
+            })
+        });
+
+        let setting = match setting {
+            Ok(setting) => setting,
+            Err(err) => liab!("line {}: {}", line_number, error_message(&err)),
+        };
+
+        if let Some(earlier) = settings
+            .iter()
+            .find(|earlier| earlier.table == setting.table && earlier.key == setting.key)
+        {
+            liab!(
+                "line {}: {:?} is already set on line {}",
+                line_number,
+                setting.key,
+                earlier.line
+            );
+        }
+
+        settings.push(setting);
+    }
+
+    anyhow::Ok(settings)
+}
+
+/// Settings read from the config file, everything that is not set keeps its default
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    keymap: Keymap,
+    theme: Theme,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut settings = arg_context!(parse_settings(text))?;
+        let mut config = Self::default();
+
+        // The theme is picked before its slots are changed, wherever its name is written
+        settings.sort_by_key(|setting| !(setting.table == "theme" && setting.key == "name"));
+
+        for setting in settings.iter() {
+            let result = match (setting.table.as_str(), setting.key.as_str()) {
+                ("keys", _) => config.keymap.bind(&setting.key, &setting.value),
+                ("theme", "name") => match &setting.value {
+                    ConfigValue::String(name) => {
+                        Theme::named(name).map(|theme| config.theme = theme)
+                    }
+                    value => Err(anyhow::anyhow!(
+                        "the theme name has to be a string, got {}",
+                        value.kind()
+                    )),
+                },
+                ("theme", slot) => config.theme.set(slot, &setting.value),
+                ("", _) => Err(anyhow::anyhow!(
+                    "{:?} has to be in a table, for example [keys]",
+                    setting.key
+                )),
+                (table, _) => Err(anyhow::anyhow!("unknown table [{}]", table)),
+            };
+
+            if let Err(err) = result {
+                liab!("line {}: {}", setting.line, error_message(&err));
+            }
+        }
+
+        anyhow::Ok(config)
+    }
+
+    /// `$XDG_CONFIG_HOME/locker/config.toml`, or `~/.config/locker/config.toml` if the variable is not set
+    pub fn default_file() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|home| !home.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+        Some(config_home.join("locker").join("config.toml"))
+    }
+
+    /// Reads the given file, or the default one if it exists
+    pub fn load(file: Option<&Path>) -> anyhow::Result<Self> {
+        let file = match file {
+            Some(file) => file.to_path_buf(),
+            None => match Self::default_file() {
+                Some(file) if file.is_file() => file,
+                _ => return anyhow::Ok(Self::default()),
+            },
+        };
+
+        let text = match std::fs::read_to_string(&file) {
+            Ok(text) => text,
+            Err(err) => liab!("{}: {}", file.display(), err),
+        };
+
+        match Self::parse(&text) {
+            Ok(config) => anyhow::Ok(config),
+            Err(err) => liab!("{}: {}", file.display(), error_message(&err)),
+        }
+    }
+
+    pub fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn string(text: &str) -> anyhow::Result<String> {
+        parse_string(text).map(|(string, _)| string)
+    }
+
+    fn parse_error(text: &str) -> String {
+        error_message(&Config::parse(text).unwrap_err())
+    }
+
+    #[test]
+    fn basic_strings_decode_escapes() {
+        assert_eq!(string(r#""a\"b\\c\td\ne\rf""#).unwrap(), "a\"b\\c\td\ne\rf");
+        assert_eq!(string(r#""\b\f""#).unwrap(), "\u{8}\u{c}");
+        assert_eq!(string(r#""caf\u00e9""#).unwrap(), "café");
+        assert_eq!(string(r#""\U0001F600""#).unwrap(), "😀");
+    }
+
+    #[test]
+    fn literal_strings_have_no_escapes() {
+        assert_eq!(string(r"'C:\path\n'").unwrap(), r"C:\path\n");
+        assert_eq!(
+            parse_string("'a' rest").unwrap(),
+            (String::from("a"), " rest")
+        );
+    }
+
+    #[test]
+    fn bad_escapes_are_reported() {
+        for (text, message) in [
+            (r#""\u00e""#, r"\u00e needs 4 hex digits"),
+            (r#""\U0001F60""#, r"\U0001F60 needs 8 hex digits"),
+            (r#""\u+0e9""#, r"\u needs 4 hex digits"),
+            (r#""\uD800""#, r"\uD800 is not a valid character"),
+            (r#""\x41""#, r"unknown escape sequence \x"),
+            (r#""open"#, "the string is not closed with \""),
+            ("bare", "expected a quoted string"),
+        ] {
+            assert_eq!(error_message(&string(text).unwrap_err()), message);
+        }
+    }
+
+    #[test]
+    fn comments_are_cut_outside_strings() {
+        assert_eq!(
+            strip_comment(r#"key = "a # b" # note"#),
+            r#"key = "a # b" "#
+        );
+        assert_eq!(strip_comment(r#"key = "a \" # b""#), r#"key = "a \" # b""#);
+        assert_eq!(strip_comment("key = 'a # b'"), "key = 'a # b'");
+        assert_eq!(strip_comment("# only a comment"), "");
+    }
+
+    #[test]
+    fn keys_are_bare_or_quoted() {
+        assert_eq!(
+            parse_key("  ctrl-c = 1").unwrap(),
+            (String::from("ctrl-c"), " = 1")
+        );
+        assert_eq!(
+            parse_key(r#""a b" = 1"#).unwrap(),
+            (String::from("a b"), " = 1")
+        );
+        assert_eq!(
+            error_message(&parse_key("a.b = 1").unwrap_err()),
+            "\"a.b\" is not a valid key, put it in quotes if it has characters other than letters, digits, _ and -"
+        );
+    }
+
+    #[test]
+    fn values_are_strings_integers_or_booleans() {
+        assert_eq!(
+            parse_value(r#" "x" "#).unwrap(),
+            ConfigValue::String(String::from("x"))
+        );
+        assert_eq!(parse_value("1_000").unwrap(), ConfigValue::Integer(1000));
+        assert_eq!(parse_value("-3").unwrap(), ConfigValue::Integer(-3));
+        assert_eq!(parse_value("true").unwrap(), ConfigValue::Boolean(true));
+        assert_eq!(parse_value("false").unwrap(), ConfigValue::Boolean(false));
+
+        for (text, message) in [
+            ("", "the value is missing"),
+            (r#""x" y"#, "unexpected \"y\" after the value"),
+            (
+                "1.5",
+                "\"1.5\" is not a supported value, use a quoted string, an integer or a boolean",
+            ),
+        ] {
+            assert_eq!(error_message(&parse_value(text).unwrap_err()), message);
+        }
+    }
+
+    #[test]
+    fn settings_keep_their_tables_and_lines() {
+        let settings = parse_settings("top = 1\n\n[keys] # bindings\nq = \"quit\"\n").unwrap();
+
+        assert_eq!(
+            settings,
+            vec![
+                Setting {
+                    table: String::default(),
+                    key: String::from("top"),
+                    value: ConfigValue::Integer(1),
+                    line: 1,
+                },
+                Setting {
+                    table: String::from("keys"),
+                    key: String::from("q"),
+                    value: ConfigValue::String(String::from("quit")),
+                    line: 4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_errors_name_the_line() {
+        for (text, message) in [
+            ("[keys\n", "line 1: expected `[table]`"),
+            ("[keys]\nq\n", "line 2: expected `key = value` or `[table]`"),
+            (
+                "[keys]\nq = 1\nq = 2\n",
+                "line 3: \"q\" is already set on line 2",
+            ),
+            (
+                "[keys]\n\nq = \"\\u12\"\n",
+                "line 3: \\u12 needs 4 hex digits",
+            ),
+        ] {
+            assert_eq!(error_message(&parse_settings(text).unwrap_err()), message);
+        }
+    }
+
+    #[test]
+    fn config_errors_name_the_line() {
+        assert_eq!(
+            parse_error("name = \"dark\""),
+            "line 1: \"name\" has to be in a table, for example [keys]"
+        );
+        assert_eq!(
+            parse_error("\n[colors]\na = 1"),
+            "line 3: unknown table [colors]"
+        );
+        assert_eq!(
+            parse_error("[theme]\nname = 1"),
+            "line 2: the theme name has to be a string, got an integer"
+        );
+    }
+
+    #[test]
+    fn theme_name_is_applied_before_slots() {
+        let config = Config::parse("[theme]\nstatus_bar = \"red\"\nname = \"mono\"\n").unwrap();
+        let mut theme = Theme::mono();
+        theme
+            .set("status_bar", &ConfigValue::String(String::from("red")))
+            .unwrap();
+
+        assert_eq!(config.theme(), &theme);
+    }
+}
@@ -0,0 +1,218 @@
+use impl_helper::ImplHelper;
+use memchr::{memchr, memrchr};
+use std::fmt;
+
+/// Characters of a line printed by `Diagnostic::report`
+const REPORT_WIDTH: usize = 80;
+
+/// Line and column in the source, both start from 1
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ImplHelper)]
+pub struct Mark {
+    #[helper(get)]
+    offset: usize,
+
+    #[helper(get)]
+    line: usize,
+
+    #[helper(get)]
+    column: usize,
+}
+
+impl Mark {
+    /// The offset can be the length of the source, the end of the input
+    pub fn new(source: &[u8], offset: usize) -> Self {
+        let offset = offset.min(source.len());
+        let before = &source[..offset];
+
+        let line = before.iter().filter(|byte| **byte == b'\n').count() + 1;
+        let line_start = memrchr(b'\n', before).map_or(0, |newline| newline + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+
+        Self {
+            offset,
+            line,
+            column,
+        }
+    }
+
+    /// Line the mark is on, without the line break
+    pub fn source_line<'s>(&self, source: &'s [u8]) -> &'s [u8] {
+        let start = memrchr(b'\n', &source[..self.offset]).map_or(0, |newline| newline + 1);
+        let finish = memchr(b'\n', &source[self.offset..])
+            .map_or(source.len(), |newline| self.offset + newline);
+
+        &source[start..finish]
+    }
+}
+
+/// Line of the source as it is shown, with tabs as single spaces
+pub fn display_line(line: &[u8]) -> String {
+    String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)).replace('\t', " ")
+}
+
+/// Part of the line around the column that fits into the width, with the column moved into it
+pub fn clip_around(line: &str, column: usize, width: usize) -> (String, usize) {
+    let characters = line.chars().collect::<Vec<char>>();
+    let width = width.max(3);
+
+    if characters.len() <= width {
+        return (line.to_string(), column);
+    }
+
+    let start = column
+        .saturating_sub(1 + width / 2)
+        .min(characters.len() - width);
+    let finish = start + width;
+
+    let mut clipped = characters[start..finish].to_vec();
+
+    if finish < characters.len() {
+        clipped[width - 1] = '…';
+    }
+
+    if start > 0 {
+        clipped[0] = '…';
+    }
+
+    (clipped.into_iter().collect(), column - start)
+}
+
+impl fmt::Display for Mark {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Mistake in the source, found while reading it
+#[derive(Debug, Default, Clone, PartialEq, Eq, ImplHelper)]
+pub struct Diagnostic {
+    #[helper(get)]
+    mark: Mark,
+
+    /// What could be written at the mark, for example "`:`" or "a value"
+    #[helper(get)]
+    expected: String,
+
+    /// What is written there instead
+    #[helper(get)]
+    found: String,
+
+    /// Start of the unfinished part, for example the bracket that is not closed
+    #[helper(get)]
+    related: Option<(Mark, String)>,
+}
+
+impl Diagnostic {
+    pub fn new(source: &[u8], offset: usize, expected: String, found: String) -> Self {
+        // The end of the input is marked after the last character, not on the empty lines after it
+        let offset = match offset >= source.len() {
+            true => source
+                .iter()
+                .rposition(|byte| !byte.is_ascii_whitespace())
+                .map_or(0, |last| last + 1),
+            false => offset,
+        };
+
+        Self {
+            mark: Mark::new(source, offset),
+            expected,
+            found,
+            related: None,
+        }
+    }
+
+    /// Adds a hint at another place, for example "unclosed `{`"
+    pub fn related_set(mut self, source: &[u8], offset: usize, hint: &str) -> Self {
+        self.related = Some((Mark::new(source, offset), hint.to_string()));
+        self
+    }
+
+    pub fn hint(&self) -> Option<String> {
+        self.related
+            .as_ref()
+            .map(|(mark, hint)| format!("{} opened at {}", hint, mark))
+    }
+
+    /// Diagnostic the error was caused by, if it was
+    pub fn find(err: &anyhow::Error) -> Option<&Self> {
+        err.root_cause().downcast_ref::<Self>()
+    }
+
+    /// Description of the character at the offset, as it is shown in `found`
+    pub fn describe(source: &[u8], offset: usize) -> String {
+        let rest = source.get(offset..).unwrap_or_default();
+        let rest = &rest[..rest.len().min(4)];
+
+        match String::from_utf8_lossy(rest).chars().next() {
+            Some(character) if character.is_whitespace() => format!("{:?}", character),
+            Some(character) => format!("`{}`", character),
+            None => String::from("the end of the input"),
+        }
+    }
+
+    /// Marks with their labels, the related one first
+    pub fn marked_lines(&self) -> Vec<(Mark, String)> {
+        let mut marks = vec![(self.mark, format!("expected {}", self.expected))];
+
+        if let Some((mark, hint)) = &self.related {
+            if mark.line != self.mark.line {
+                marks.insert(0, (*mark, hint.clone()));
+            }
+        }
+
+        marks
+    }
+
+    /// Message with the marked lines and the hint
+    pub fn report(&self, file: &str, source: &[u8]) -> String {
+        let mut report = format!("{}:{}\n", file, self);
+
+        let marks = self.marked_lines();
+        let number_width = marks
+            .iter()
+            .map(|(mark, _)| mark.line.to_string().len())
+            .max()
+            .unwrap_or_default();
+
+        for (mark, label) in marks.iter() {
+            let line = display_line(mark.source_line(source));
+            let (line, column) = clip_around(&line, mark.column, REPORT_WIDTH);
+
+            report.push_str(&format!(
+                "{:>width$} | {}\n",
+                mark.line,
+                line,
+                width = number_width
+            ));
+            report.push_str(&format!(
+                "{:>width$} | {:>column$} {}\n",
+                "",
+                "^",
+                label,
+                width = number_width,
+                column = column
+            ));
+        }
+
+        if let Some(hint) = self.hint() {
+            report.push_str(&format!("hint: {}\n", hint));
+        }
+
+        report
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            self.mark, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
@@ -0,0 +1,307 @@
+use wrap_context::{arg_context, liab};
+
+use crate::{
+    flake_lock::{moved_inputs, FlakeLock},
+    lexer::{block_content, get_array_items, get_object_items, items_to_vec, Lexer, TokenKind},
+    output::json_string,
+    reformat::{reformat, Layout},
+    types::{Entry, EntryRole},
+    utils::Location,
+};
+
+/// How a value differs between the old and the new file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Difference {
+    /// Key names and item indices leading to the value
+    path: Vec<String>,
+    change: Change,
+    /// Minified old value of the changed values
+    old: Option<String>,
+}
+
+enum Members {
+    Object(Vec<(String, Location)>),
+    Array(Vec<Location>),
+    Scalar,
+}
+
+fn members(source: &[u8], location: Location) -> anyhow::Result<Members> {
+    let mut lexer = Lexer::new(source, location);
+    let token = arg_context!(lexer.next_token())?;
+    let content = arg_context!(block_content(source, &token))?;
+
+    let members = match (token.kind(), content) {
+        (TokenKind::Object, Some(content)) => {
+            let items = arg_context!(get_object_items(source, content))?;
+
+            Members::Object(
+                arg_context!(items_to_vec(source, &items))?
+                    .iter()
+                    .map(|blank| (blank.name().clone(), *blank.value_location()))
+                    .collect(),
+            )
+        }
+        (TokenKind::Object, None) => Members::Object(vec![]),
+        (TokenKind::Array, Some(content)) => {
+            Members::Array(arg_context!(get_array_items(source, content))?)
+        }
+        (TokenKind::Array, None) => Members::Array(vec![]),
+        (TokenKind::LastChar, _) => liab!("Expected a value at {:?}, but got nothing", location),
+        _ => Members::Scalar,
+    };
+
+    anyhow::Ok(members)
+}
+
+fn whole(source: &[u8]) -> anyhow::Result<Location> {
+    match source.len().checked_sub(1) {
+        Some(finish) => anyhow::Ok(Location::new(0, finish)),
+        None => liab!("Provided file does not contain any data to show"),
+    }
+}
+
+/// Key names and item indices of the entries along the path, values of keys don't have a step of their own
+fn entry_path(ancestors: &[&Entry], entry: &Entry) -> Vec<String> {
+    ancestors
+        .iter()
+        .copied()
+        .chain([entry])
+        .filter_map(|entry| match entry.role() {
+            EntryRole::Key => Some(entry.name().clone()),
+            EntryRole::Item => Some(entry.path().last().copied().unwrap_or_default().to_string()),
+            EntryRole::Root | EntryRole::Value => None,
+        })
+        .collect()
+}
+
+struct Differ<'s> {
+    old: &'s [u8],
+    new: &'s [u8],
+    differences: Vec<Difference>,
+}
+
+impl<'s> Differ<'s> {
+    /// Records the whole value as added or removed and returns its text
+    fn whole_value(
+        &mut self,
+        path: &[String],
+        change: Change,
+        location: Location,
+    ) -> anyhow::Result<String> {
+        let source = if change == Change::Removed {
+            self.old
+        } else {
+            self.new
+        };
+
+        self.differences.push(Difference {
+            path: path.to_vec(),
+            change,
+            old: None,
+        });
+
+        reformat(source, &location, Layout::Minify)
+    }
+
+    /// Records the differences under the path and returns the part of the document that shows them.
+    /// Arrays with differences become objects keyed by the indices, so unchanged items can be left out.
+    fn compare(
+        &mut self,
+        path: &mut Vec<String>,
+        old: Location,
+        new: Location,
+    ) -> anyhow::Result<Option<String>> {
+        let members = (
+            arg_context!(members(self.old, old))?,
+            arg_context!(members(self.new, new))?,
+        );
+
+        let pairs = match members {
+            (Members::Object(old_members), Members::Object(new_members)) => {
+                let mut pairs = vec![];
+
+                for (name, old_location) in old_members.iter() {
+                    let new_location = new_members
+                        .iter()
+                        .find(|(new_name, _)| new_name == name)
+                        .map(|(_, location)| *location);
+
+                    pairs.push((name.clone(), Some(*old_location), new_location));
+                }
+
+                for (name, new_location) in new_members.iter() {
+                    if !old_members.iter().any(|(old_name, _)| old_name == name) {
+                        pairs.push((name.clone(), None, Some(*new_location)));
+                    }
+                }
+
+                pairs
+            }
+            (Members::Array(old_items), Members::Array(new_items)) => {
+                (0..old_items.len().max(new_items.len()))
+                    .map(|i| {
+                        (
+                            i.to_string(),
+                            old_items.get(i).copied(),
+                            new_items.get(i).copied(),
+                        )
+                    })
+                    .collect()
+            }
+            _ => {
+                let old_value = arg_context!(reformat(self.old, &old, Layout::Minify))?;
+                let new_value = arg_context!(reformat(self.new, &new, Layout::Minify))?;
+
+                if old_value == new_value {
+                    return anyhow::Ok(None);
+                }
+
+                self.differences.push(Difference {
+                    path: path.clone(),
+                    change: Change::Changed,
+                    old: Some(old_value),
+                });
+
+                return anyhow::Ok(Some(new_value));
+            }
+        };
+
+        let mut parts = vec![];
+
+        for (name, old_location, new_location) in pairs {
+            path.push(name.clone());
+
+            let part = match (old_location, new_location) {
+                (Some(old_location), Some(new_location)) => {
+                    arg_context!(self.compare(path, old_location, new_location))?
+                }
+                (Some(old_location), None) => Some(arg_context!(self.whole_value(
+                    path,
+                    Change::Removed,
+                    old_location
+                ))?),
+                (None, Some(new_location)) => Some(arg_context!(self.whole_value(
+                    path,
+                    Change::Added,
+                    new_location
+                ))?),
+                (None, None) => None,
+            };
+
+            path.pop();
+
+            if let Some(part) = part {
+                parts.push(format!("{}:{}", json_string(&name), part));
+            }
+        }
+
+        if parts.is_empty() {
+            anyhow::Ok(None)
+        } else {
+            anyhow::Ok(Some(format!("{{{}}}", parts.join(","))))
+        }
+    }
+}
+
+/// Differences between two files, keys are aligned by name and array items by index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff {
+    /// JSON document that holds only the values that differ
+    document: String,
+    differences: Vec<Difference>,
+    /// Nodes of flake.lock files that were locked at another revision, with the description of the move
+    moves: Vec<(String, String)>,
+}
+
+impl Diff {
+    pub fn new(old: &[u8], new: &[u8]) -> anyhow::Result<Self> {
+        let mut differ = Differ {
+            old,
+            new,
+            differences: vec![],
+        };
+
+        let mut path = Vec::<String>::default();
+        let old_location = arg_context!(whole(old))?;
+        let new_location = arg_context!(whole(new))?;
+        let document = arg_context!(differ.compare(&mut path, old_location, new_location))?
+            .unwrap_or_default();
+
+        let moves = match (
+            arg_context!(FlakeLock::parse(old))?,
+            arg_context!(FlakeLock::parse(new))?,
+        ) {
+            (Some(old), Some(new)) => moved_inputs(&old, &new),
+            _ => vec![],
+        };
+
+        anyhow::Ok(Self {
+            document,
+            differences: differ.differences,
+            moves,
+        })
+    }
+
+    pub fn document(&self) -> &str {
+        &self.document
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Which flake.lock inputs moved and by how many days, one per line
+    pub fn summary(&self) -> String {
+        let width = self
+            .moves
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        self.moves
+            .iter()
+            .map(|(name, description)| format!("{:width$}  {}\n", name, description, width = width))
+            .collect()
+    }
+
+    /// Change of the entry or of the value it's in, `ancestors` are the entries on the way to it
+    pub fn change(&self, ancestors: &[&Entry], entry: &Entry) -> Option<Change> {
+        let path = entry_path(ancestors, entry);
+
+        self.differences
+            .iter()
+            .find(|difference| path.starts_with(&difference.path))
+            .map(|difference| difference.change)
+    }
+
+    /// Old value of the changed entries and the moves of flake.lock nodes
+    pub fn annotate(&self, ancestors: &[&Entry], entry: &Entry) -> Option<String> {
+        if entry.role() == &EntryRole::Value {
+            return None;
+        }
+
+        let path = entry_path(ancestors, entry);
+
+        if let [nodes, node] = path.as_slice() {
+            if nodes == "nodes" {
+                if let Some((_, description)) = self.moves.iter().find(|(name, _)| name == node) {
+                    return Some(description.clone());
+                }
+            }
+        }
+
+        self.differences
+            .iter()
+            .find(|difference| difference.path == path)
+            .and_then(|difference| difference.old.as_ref())
+            .map(|old| format!("was {}", old))
+    }
+}
@@ -0,0 +1,514 @@
+use wrap_context::{arg_context, liab, raw_context};
+
+use crate::{
+    lexer::{block_content, get_array_items, get_object_items, Lexer, Structure, Token, TokenKind},
+    node::{Node, ReadMode},
+    output::json_string,
+    search::find_errors,
+    utils::{Location, SliceFromLocation},
+};
+
+/// Number of nodes built at once while checking an edited source
+const VALIDATION_PAGE_SIZE: usize = 1024;
+
+/// Replacement of a byte range of the source, everything around it is kept as it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Splice {
+    start: usize,
+    removed: Vec<u8>,
+    inserted: Vec<u8>,
+}
+
+impl Splice {
+    pub fn new(source: &[u8], location: &Location, inserted: Vec<u8>) -> Self {
+        Self {
+            start: *location.start(),
+            removed: source.slice(location).to_vec(),
+            inserted,
+        }
+    }
+
+    pub fn apply(&self, source: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(source.len() + self.inserted.len());
+        result.extend_from_slice(&source[..self.start]);
+        result.extend_from_slice(&self.inserted);
+        result.extend_from_slice(&source[self.start + self.removed.len()..]);
+
+        result
+    }
+
+    /// Splice that only adds the bytes at the position
+    pub fn insert(start: usize, inserted: Vec<u8>) -> Self {
+        Self {
+            start,
+            removed: vec![],
+            inserted,
+        }
+    }
+
+    /// Whether the source stays the same after the splice
+    pub fn changes_nothing(&self) -> bool {
+        self.removed == self.inserted
+    }
+
+    /// Splice that brings the source back to how it was before this one
+    pub fn inverse(&self) -> Self {
+        Self {
+            start: self.start,
+            removed: self.inserted.clone(),
+            inserted: self.removed.clone(),
+        }
+    }
+}
+
+/// Checks that the text is a single scalar value, for example `"text"`, `42`, `true` or `null`
+pub fn validate_scalar(text: &str) -> anyhow::Result<()> {
+    let source = text.trim().as_bytes();
+
+    if source.is_empty() {
+        liab!("The value can't be empty");
+    }
+
+    let mut lexer = Lexer::new(source, Location::new(0, source.len() - 1));
+    let token = arg_context!(lexer.next_token())?;
+
+    match token.kind() {
+        TokenKind::String
+        | TokenKind::Number
+        | TokenKind::Boolean
+        | TokenKind::Null
+        | TokenKind::BareWord => {}
+        _ => liab!("{:?} is not a scalar value", text),
+    }
+
+    // The lexer skips comments, which would hide the rest of the line after the splice
+    if token.location().finish() + 1 != source.len() {
+        liab!("{:?} is more than one value", text);
+    }
+
+    anyhow::Ok(())
+}
+
+/// Checks that the whole source can still be read.
+/// With `--recover` the source may have mistakes, as long as there are not more of them than in the one before the edit.
+pub fn validate_source(source: &[u8], previous: &[u8], mode: ReadMode) -> anyhow::Result<()> {
+    if *mode.recover() {
+        let errors = arg_context!(find_errors(source, mode))?.len();
+
+        if errors > arg_context!(find_errors(previous, mode))?.len() {
+            liab!("The edit would break another part of the file");
+        }
+
+        return anyhow::Ok(());
+    }
+
+    let mut root = arg_context!(Node::root(source, VALIDATION_PAGE_SIZE, mode))?;
+    raw_context!(root.walk(&mut |_| anyhow::Ok(())))?;
+
+    anyhow::Ok(())
+}
+
+/// Start of the first comment after the comma in the gap, `finish` if there is none
+fn comments_start(source: &[u8], start: usize, finish: usize) -> usize {
+    if start >= finish {
+        return finish;
+    }
+
+    let mut structure = Structure::new(source, Location::new(start, finish - 1));
+    let comma = structure
+        .by_ref()
+        .find(|position| source[*position] == b',')
+        .unwrap_or(start);
+    structure.by_ref().for_each(drop);
+
+    structure
+        .comments()
+        .iter()
+        .map(|comment| *comment.start())
+        .find(|comment| *comment >= comma)
+        .unwrap_or(finish)
+}
+
+/// Object or array with the locations of its members, from the start of the key to the end of the value
+#[derive(Debug, Clone)]
+pub struct Block {
+    token: Token,
+    members: Vec<Location>,
+}
+
+impl Block {
+    /// Reads the block that starts at the location, which may be followed by anything
+    pub fn new(source: &[u8], location: Location) -> anyhow::Result<Self> {
+        let mut lexer = Lexer::new(source, location);
+        let token = arg_context!(lexer.next_token())?;
+        let content = arg_context!(block_content(source, &token))?;
+
+        let members = match (token.kind(), content) {
+            (TokenKind::Object, Some(content)) => arg_context!(get_object_items(source, content))?,
+            (TokenKind::Array, Some(content)) => arg_context!(get_array_items(source, content))?,
+            (TokenKind::Object | TokenKind::Array, None) => vec![],
+            _ => liab!(
+                "Only objects and arrays have members, got {:?}",
+                token.kind()
+            ),
+        };
+
+        anyhow::Ok(Self { token, members })
+    }
+
+    pub fn is_object(&self) -> bool {
+        self.token.kind() == &TokenKind::Object
+    }
+
+    fn member(&self, index: usize) -> anyhow::Result<&Location> {
+        match self.members.get(index) {
+            Some(member) => anyhow::Ok(member),
+            None => liab!("The block has no member at index {}", index),
+        }
+    }
+
+    /// Position right after the previous member or the opening bracket
+    fn gap_start(&self, index: usize) -> usize {
+        match index.checked_sub(1) {
+            Some(previous) => *self.members[previous].finish() + 1,
+            None => *self.token.location().start() + 1,
+        }
+    }
+
+    /// Whitespace written before the members, so new ones get the same indentation
+    fn separator<'s>(&self, source: &'s [u8], index: usize) -> &'s [u8] {
+        let between = match (index.checked_sub(1), self.members.get(1)) {
+            (Some(previous), _) => Some((self.members[previous], self.members[index])),
+            (None, Some(next)) => Some((self.members[0], *next)),
+            (None, None) => None,
+        };
+
+        let gap = match between {
+            Some((before, after)) => &source[*before.finish() + 1..*after.start()],
+            None => &source[*self.token.location().start() + 1..*self.members[index].start()],
+        };
+
+        // Only the whitespace right before the member
+        let start = gap
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map_or(0, |last| last + 1);
+
+        &gap[start..]
+    }
+
+    /// Removes the member together with one of the commas around it
+    pub fn delete(&self, source: &[u8], index: usize) -> anyhow::Result<Splice> {
+        let member = arg_context!(self.member(index))?;
+
+        let location = match (index.checked_sub(1), self.members.get(index + 1)) {
+            // Whatever was between the brackets goes as well, so the block becomes `{}` or `[]`
+            (None, None) => Location::new(
+                *self.token.location().start() + 1,
+                *self.token.location().finish() - 1,
+            ),
+            // Comments written about the member go with it, those about the next one stay
+            (_, Some(next)) => Location::new(
+                comments_start(source, self.gap_start(index), *member.start()),
+                comments_start(source, *member.finish() + 1, *next.start()) - 1,
+            ),
+            (Some(previous), None) => {
+                Location::new(*self.members[previous].finish() + 1, *member.finish())
+            }
+        };
+
+        anyhow::Ok(Splice::new(source, &location, vec![]))
+    }
+
+    /// Location of the key of the object member, including the quotes
+    pub fn key_location(&self, source: &[u8], index: usize) -> anyhow::Result<Location> {
+        if !self.is_object() {
+            liab!("Only object members have keys");
+        }
+
+        let member = arg_context!(self.member(index))?;
+        let key_token = arg_context!(Lexer::new(source, *member).next_token())?;
+
+        anyhow::Ok(*key_token.location())
+    }
+
+    /// Replaces the key of the object member, the name is written as a JSON string
+    pub fn rename(&self, source: &[u8], index: usize, key: &str) -> anyhow::Result<Splice> {
+        if key.is_empty() {
+            liab!("The key can't be empty");
+        }
+
+        let location = arg_context!(self.key_location(source, index))?;
+
+        anyhow::Ok(Splice::new(
+            source,
+            &location,
+            json_string(key).into_bytes(),
+        ))
+    }
+
+    /// Inserts the text as a new member after the one at the index, on a line of its own if the members are
+    pub fn insert_after(&self, source: &[u8], index: usize, text: &str) -> anyhow::Result<Splice> {
+        let member = arg_context!(self.member(index))?;
+
+        let mut inserted = vec![b','];
+        inserted.extend_from_slice(self.separator(source, index));
+        inserted.extend_from_slice(text.trim().as_bytes());
+
+        anyhow::Ok(Splice::insert(*member.finish() + 1, inserted))
+    }
+
+    /// Inserts the text as the first member
+    pub fn insert_first(&self, source: &[u8], text: &str) -> anyhow::Result<Splice> {
+        let Some(first) = self.members.first() else {
+            let start = *self.token.location().start() + 1;
+            let finish = *self.token.location().finish();
+            let inserted = text.trim().as_bytes().to_vec();
+
+            // Whatever was between the brackets of the empty block is replaced
+            return match finish.checked_sub(1).filter(|last| start <= *last) {
+                Some(last) => {
+                    anyhow::Ok(Splice::new(source, &Location::new(start, last), inserted))
+                }
+                None => anyhow::Ok(Splice::insert(start, inserted)),
+            };
+        };
+
+        let mut inserted = text.trim().as_bytes().to_vec();
+        inserted.push(b',');
+        inserted.extend_from_slice(self.separator(source, 0));
+
+        anyhow::Ok(Splice::insert(*first.start(), inserted))
+    }
+
+    /// Inserts a copy of the member right after it
+    pub fn duplicate(&self, source: &[u8], index: usize) -> anyhow::Result<Splice> {
+        let member = arg_context!(self.member(index))?;
+        let text = arg_context!(std::str::from_utf8(source.slice(member)))?;
+
+        arg_context!(self.insert_after(source, index, text))
+    }
+}
+
+/// Checks that the text is a single member that fits into the block, `key: value` for objects and a value for arrays
+pub fn validate_member(text: &str, object: bool) -> anyhow::Result<()> {
+    let source = text.trim().as_bytes();
+
+    if source.is_empty() {
+        liab!("The member can't be empty");
+    }
+
+    let location = Location::new(0, source.len() - 1);
+    let members = if object {
+        arg_context!(get_object_items(source, location))?
+    } else {
+        arg_context!(get_array_items(source, location))?
+    };
+
+    match (members.as_slice(), object) {
+        ([_], _) => anyhow::Ok(()),
+        (_, true) => liab!("Expected one `key: value` member, got {:?}", text),
+        (_, false) => liab!("Expected one value, got {:?}", text),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const OBJECT: &str = "{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}";
+    const ARRAY: &str = "[1, 2, 3]";
+
+    fn block(source: &str) -> Block {
+        Block::new(source.as_bytes(), Location::new(0, source.len() - 1)).unwrap()
+    }
+
+    fn edited(source: &str, splice: anyhow::Result<Splice>) -> String {
+        String::from_utf8(splice.unwrap().apply(source.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn delete_fixes_commas() {
+        let object = block(OBJECT);
+        let array = block(ARRAY);
+
+        assert_eq!(
+            edited(OBJECT, object.delete(OBJECT.as_bytes(), 0)),
+            "{\n  \"b\": 2,\n  \"c\": 3\n}"
+        );
+        assert_eq!(
+            edited(OBJECT, object.delete(OBJECT.as_bytes(), 1)),
+            "{\n  \"a\": 1,\n  \"c\": 3\n}"
+        );
+        assert_eq!(
+            edited(OBJECT, object.delete(OBJECT.as_bytes(), 2)),
+            "{\n  \"a\": 1,\n  \"b\": 2\n}"
+        );
+        assert_eq!(edited(ARRAY, array.delete(ARRAY.as_bytes(), 0)), "[2, 3]");
+        assert_eq!(edited(ARRAY, array.delete(ARRAY.as_bytes(), 1)), "[1, 3]");
+        assert_eq!(edited(ARRAY, array.delete(ARRAY.as_bytes(), 2)), "[1, 2]");
+    }
+
+    #[test]
+    fn delete_last_member_empties_block() {
+        let source = "{\n  \"a\": 1\n}";
+
+        assert_eq!(
+            edited(source, block(source).delete(source.as_bytes(), 0)),
+            "{}"
+        );
+        assert!(block(ARRAY).delete(ARRAY.as_bytes(), 3).is_err());
+    }
+
+    #[test]
+    fn delete_takes_comments_about_member() {
+        let source = "{\n  // about a\n  \"a\": 1, // about b\n  \"b\": 2\n}";
+
+        assert_eq!(
+            edited(source, block(source).delete(source.as_bytes(), 0)),
+            "{\n  // about b\n  \"b\": 2\n}"
+        );
+    }
+
+    #[test]
+    fn rename_quotes_and_escapes() {
+        let object = block(OBJECT);
+
+        assert_eq!(
+            edited(OBJECT, object.rename(OBJECT.as_bytes(), 1, "new")),
+            "{\n  \"a\": 1,\n  \"new\": 2,\n  \"c\": 3\n}"
+        );
+        assert_eq!(
+            edited(OBJECT, object.rename(OBJECT.as_bytes(), 0, "say \"hi\"\n")),
+            "{\n  \"say \\\"hi\\\"\\n\": 1,\n  \"b\": 2,\n  \"c\": 3\n}"
+        );
+    }
+
+    #[test]
+    fn rename_rejects_empty_keys_and_arrays() {
+        assert!(block(OBJECT).rename(OBJECT.as_bytes(), 0, "").is_err());
+        assert!(block(ARRAY).rename(ARRAY.as_bytes(), 0, "key").is_err());
+    }
+
+    #[test]
+    fn insert_after_keeps_indentation() {
+        let object = block(OBJECT);
+        let array = block(ARRAY);
+
+        assert_eq!(
+            edited(
+                OBJECT,
+                object.insert_after(OBJECT.as_bytes(), 0, "\"x\": 0")
+            ),
+            "{\n  \"a\": 1,\n  \"x\": 0,\n  \"b\": 2,\n  \"c\": 3\n}"
+        );
+        assert_eq!(
+            edited(
+                OBJECT,
+                object.insert_after(OBJECT.as_bytes(), 2, " \"x\": 0 ")
+            ),
+            "{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3,\n  \"x\": 0\n}"
+        );
+        assert_eq!(
+            edited(ARRAY, array.insert_after(ARRAY.as_bytes(), 1, "9")),
+            "[1, 2, 9, 3]"
+        );
+        assert_eq!(
+            edited(ARRAY, array.insert_after(ARRAY.as_bytes(), 2, "9")),
+            "[1, 2, 3, 9]"
+        );
+    }
+
+    #[test]
+    fn insert_first_keeps_indentation() {
+        assert_eq!(
+            edited(
+                OBJECT,
+                block(OBJECT).insert_first(OBJECT.as_bytes(), "\"x\": 0")
+            ),
+            "{\n  \"x\": 0,\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}"
+        );
+        assert_eq!(
+            edited(ARRAY, block(ARRAY).insert_first(ARRAY.as_bytes(), "0")),
+            "[0, 1, 2, 3]"
+        );
+    }
+
+    #[test]
+    fn insert_first_into_empty_blocks() {
+        for (source, text, expected) in [
+            ("{}", "\"x\": 0", "{\"x\": 0}"),
+            ("[]", "0", "[0]"),
+            ("{\n}", "\"x\": 0", "{\"x\": 0}"),
+            ("[ ]", "0", "[0]"),
+        ] {
+            assert_eq!(
+                edited(source, block(source).insert_first(source.as_bytes(), text)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_copies_member() {
+        assert_eq!(
+            edited(OBJECT, block(OBJECT).duplicate(OBJECT.as_bytes(), 0)),
+            "{\n  \"a\": 1,\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}"
+        );
+        assert_eq!(
+            edited(ARRAY, block(ARRAY).duplicate(ARRAY.as_bytes(), 1)),
+            "[1, 2, 2, 3]"
+        );
+        assert_eq!(
+            edited(ARRAY, block(ARRAY).duplicate(ARRAY.as_bytes(), 2)),
+            "[1, 2, 3, 3]"
+        );
+    }
+
+    #[test]
+    fn splice_inverse_restores_source() {
+        let source = ARRAY.as_bytes();
+
+        for splice in [
+            Splice::new(source, &Location::new(1, 1), b"10".to_vec()),
+            Splice::new(source, &Location::new(1, 5), b"4".to_vec()),
+            Splice::new(source, &Location::new(0, 8), b"{}".to_vec()),
+            Splice::insert(9, b"\n".to_vec()),
+            Splice::insert(1, vec![]),
+        ] {
+            let edited = splice.apply(source);
+
+            assert_eq!(splice.inverse().apply(&edited), source);
+            assert_eq!(splice.inverse().inverse(), splice);
+        }
+    }
+
+    #[test]
+    fn splice_apply() {
+        let source = ARRAY.as_bytes();
+
+        assert_eq!(
+            Splice::new(source, &Location::new(4, 4), b"\"two\"".to_vec()).apply(source),
+            b"[1, \"two\", 3]"
+        );
+        assert_eq!(
+            Splice::insert(1, b"0, ".to_vec()).apply(source),
+            b"[0, 1, 2, 3]"
+        );
+        assert!(Splice::new(source, &Location::new(4, 4), b"2".to_vec()).changes_nothing());
+        assert!(!Splice::insert(0, b" ".to_vec()).changes_nothing());
+    }
+
+    #[test]
+    fn scalar_edits_refuse_trailing_text() {
+        for text in ["1", " \"x // y\" ", "true", "-2.5e3"] {
+            assert!(validate_scalar(text).is_ok(), "{}", text);
+        }
+
+        for text in ["1 // x", "1 /* x */", "1 2", "\"a\", \"b\"", "{}", ""] {
+            assert!(validate_scalar(text).is_err(), "{}", text);
+        }
+    }
+}
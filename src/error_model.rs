@@ -0,0 +1,82 @@
+use impl_helper::ImplHelper;
+use ratatui::layout::Rect;
+use std::collections::BTreeMap;
+
+use crate::diagnostic::{display_line, Diagnostic};
+
+/// Lines shown above each marked line
+const CONTEXT_LINES: usize = 2;
+
+/// Line of the source shown in the error view
+#[derive(Default, Debug, Clone, PartialEq, Eq, ImplHelper)]
+pub struct ExcerptLine {
+    #[helper(get)]
+    number: usize,
+
+    #[helper(get)]
+    text: String,
+
+    /// Column and label of the caret drawn under the line
+    #[helper(get)]
+    caret: Option<(usize, String)>,
+}
+
+/// Full-screen view of a mistake in the file
+#[derive(Default, Debug, Clone, PartialEq, Eq, ImplHelper)]
+pub struct ErrorModel {
+    #[helper(all)]
+    area: Rect,
+
+    #[helper(get)]
+    file: String,
+
+    #[helper(get)]
+    diagnostic: Diagnostic,
+
+    /// Whether the file was read with `--recover`, otherwise the option is suggested
+    #[helper(all)]
+    recover: bool,
+
+    /// Marked lines with a few lines before them, in the order they are written
+    #[helper(get)]
+    lines: Vec<ExcerptLine>,
+}
+
+impl ErrorModel {
+    pub fn new(file: &str, source: &[u8], diagnostic: Diagnostic) -> Self {
+        let mut carets = BTreeMap::<usize, Option<(usize, String)>>::default();
+
+        for (mark, label) in diagnostic.marked_lines() {
+            for number in mark.line().saturating_sub(CONTEXT_LINES).max(1)..*mark.line() {
+                carets.entry(number).or_insert(None);
+            }
+
+            carets.insert(*mark.line(), Some((*mark.column(), label)));
+        }
+
+        let last_number = carets.keys().last().copied().unwrap_or_default();
+
+        let lines = source
+            .split(|byte| *byte == b'\n')
+            .take(last_number)
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let caret = carets.get(&(i + 1))?;
+
+                Some(ExcerptLine {
+                    number: i + 1,
+                    text: display_line(line),
+                    caret: caret.clone(),
+                })
+            })
+            .collect();
+
+        Self {
+            area: Rect::default(),
+            file: file.to_string(),
+            diagnostic,
+            recover: false,
+            lines,
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use ratatui::{
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{diagnostic::clip_around, error_model::ErrorModel, render::Render};
+
+#[derive(Debug, Default)]
+pub struct ErrorView<'a> {
+    area: Rect,
+    paragraph: Paragraph<'a>,
+}
+
+impl<'a> From<&ErrorModel> for ErrorView<'a> {
+    fn from(model: &ErrorModel) -> Self {
+        let area = *model.area();
+
+        let number_width = model
+            .lines()
+            .iter()
+            .map(|line| line.number().to_string().len())
+            .max()
+            .unwrap_or_default();
+        // Borders, the margin, the line numbers and the separator
+        let text_width = usize::from(area.width).saturating_sub(number_width + 6);
+
+        let mut lines = vec![
+            Line::from(format!(" {}", model.diagnostic())).bold(),
+            Line::default(),
+        ];
+
+        let mut previous_number = None;
+
+        for line in model.lines().iter() {
+            if previous_number.is_some_and(|previous| previous + 1 < *line.number()) {
+                lines.push(Line::from(format!(" {:>width$} ⋮", "", width = number_width)).dim());
+            }
+
+            previous_number = Some(*line.number());
+
+            let caret_column = line.caret().as_ref().map_or(1, |(column, _)| *column);
+            let (text, column) = clip_around(line.text(), caret_column, text_width);
+
+            lines.push(Line::from(vec![
+                Span::from(format!(
+                    " {:>width$} │ ",
+                    line.number(),
+                    width = number_width
+                ))
+                .dim(),
+                Span::from(text),
+            ]));
+
+            if let Some((_, label)) = line.caret() {
+                lines.push(Line::from(vec![
+                    Span::from(format!(" {:>width$} │ ", "", width = number_width)).dim(),
+                    Span::from(format!("{:>column$} {}", "^", label, column = column)).bold(),
+                ]));
+            }
+        }
+
+        if let Some(hint) = model.diagnostic().hint() {
+            lines.push(Line::default());
+            lines.push(Line::from(format!(" hint: {}", hint)));
+        }
+
+        if !model.recover() {
+            lines.push(Line::default());
+            lines.push(
+                Line::from(" Run with --recover to browse the parts of the file that can be read")
+                    .dim(),
+            );
+        }
+
+        let block = Block::bordered()
+            .title(format!(" Could not read {} ", model.file()))
+            .title_bottom(Line::from(" Press any key to quit ").right_aligned().dim());
+
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+
+        Self { area, paragraph }
+    }
+}
+
+impl<'a> Render for ErrorView<'a> {
+    fn render(&mut self, frame: &mut Frame) {
+        frame.render_widget(Clear, self.area);
+        frame.render_widget(&self.paragraph, self.area);
+    }
+}
@@ -0,0 +1,777 @@
+use clap::ValueEnum;
+use std::{
+    str::from_utf8,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use wrap_context::{arg_context, liab};
+
+use crate::{
+    lexer::{
+        block_content, decode_string, get_array_items, get_object_items, items_to_vec, Lexer,
+        TokenKind,
+    },
+    output::json_string,
+    types::{Entry, EntryRole},
+    utils::{Location, SliceFromLocation},
+};
+
+/// `follows` chains longer than this are considered to be cyclic
+const MAX_FOLLOWS_DEPTH: usize = 64;
+
+const SHORT_REV_LENGTH: usize = 12;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Year, month and day of the days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| i64::try_from(duration.as_secs()).unwrap_or(i64::MAX))
+        .unwrap_or_default()
+}
+
+/// ISO date of a Unix timestamp and how long ago it was, for example "2025-06-30, 142 days ago"
+fn describe_timestamp(timestamp: &str, now: i64) -> Option<String> {
+    let timestamp = timestamp.parse::<i64>().ok()?;
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(SECONDS_PER_DAY));
+
+    let age = match (now - timestamp).div_euclid(SECONDS_PER_DAY) {
+        days if days < 0 => String::from("in the future"),
+        0 => String::from("today"),
+        1 => String::from("1 day ago"),
+        days => format!("{} days ago", days),
+    };
+
+    Some(format!("{:04}-{:02}-{:02}, {}", year, month, day, age))
+}
+
+fn join_notes<const N: usize>(notes: [Option<String>; N]) -> Option<String> {
+    let notes = notes.into_iter().flatten().collect::<Vec<String>>();
+
+    if notes.is_empty() {
+        None
+    } else {
+        Some(notes.join(", "))
+    }
+}
+
+fn short_rev(rev: &str) -> String {
+    rev.chars().take(SHORT_REV_LENGTH).collect()
+}
+
+/// Fully read JSON value, flake.lock files are small enough to keep them in memory this way
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Object(Vec<(String, Value)>),
+    Array(Vec<Value>),
+    Scalar(String),
+}
+
+impl Value {
+    fn read(source: &[u8], location: Location) -> anyhow::Result<Self> {
+        let mut lexer = Lexer::new(source, location);
+        let token = arg_context!(lexer.next_token())?;
+        let content = arg_context!(block_content(source, &token))?;
+
+        let value = match (token.kind(), content) {
+            (TokenKind::Object, Some(content)) => {
+                let items = arg_context!(get_object_items(source, content))?;
+                let mut members = vec![];
+
+                for blank in arg_context!(items_to_vec(source, &items))? {
+                    let value = arg_context!(Self::read(source, *blank.value_location()))?;
+                    members.push((blank.name().clone(), value));
+                }
+
+                Value::Object(members)
+            }
+            (TokenKind::Object, None) => Value::Object(vec![]),
+            (TokenKind::Array, Some(content)) => {
+                let mut items = vec![];
+
+                for item in arg_context!(get_array_items(source, content))? {
+                    items.push(arg_context!(Self::read(source, item))?);
+                }
+
+                Value::Array(items)
+            }
+            (TokenKind::Array, None) => Value::Array(vec![]),
+            (TokenKind::String, Some(content)) => {
+                Value::Scalar(arg_context!(decode_string(source, &content))?)
+            }
+            (TokenKind::String, None) => Value::Scalar(String::default()),
+            _ => {
+                Value::Scalar(arg_context!(from_utf8(source.slice(token.location())))?.to_string())
+            }
+        };
+
+        anyhow::Ok(value)
+    }
+
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Scalar(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Members of an object whose values are scalars
+    fn fields(&self) -> Fields {
+        match self {
+            Value::Object(members) => members
+                .iter()
+                .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Where an input of a node points to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputRef {
+    /// Name of a node under `nodes`
+    Node(String),
+    /// Input names leading from the root node to the actual input
+    Follows(Vec<String>),
+}
+
+/// Names and values of the scalar members of an object
+type Fields = Vec<(String, String)>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakeNode {
+    name: String,
+    inputs: Vec<(String, InputRef)>,
+    locked: Fields,
+    original: Fields,
+}
+
+fn field<'f>(fields: &'f [(String, String)], name: &str) -> Option<&'f str> {
+    fields
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value.as_str())
+}
+
+impl FlakeNode {
+    pub fn locked(&self, name: &str) -> Option<&str> {
+        field(&self.locked, name)
+    }
+
+    pub fn original(&self, name: &str) -> Option<&str> {
+        field(&self.original, name)
+    }
+
+    /// Revision the node is locked at, tarballs without one are identified by their hash
+    pub fn locked_rev(&self) -> Option<&str> {
+        self.locked("rev").or(self.locked("narHash"))
+    }
+
+    /// "owner/repo@ref" of the inputs that come from github, gitlab or sourcehut
+    pub fn forge_label(&self) -> Option<String> {
+        match self.original("type")? {
+            "github" | "gitlab" | "sourcehut" => {
+                let label = format!("{}/{}", self.original("owner")?, self.original("repo")?);
+
+                match self.original("ref") {
+                    Some(reference) => Some(format!("{}@{}", label, reference)),
+                    None => Some(label),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Flake reference the node was locked from, for example "github:NixOS/nixpkgs/nixos-unstable"
+    pub fn spec(&self) -> Option<String> {
+        let kind = self.original("type")?;
+
+        let spec = match kind {
+            "github" | "gitlab" | "sourcehut" => {
+                let mut spec = format!(
+                    "{}:{}/{}",
+                    kind,
+                    self.original("owner")?,
+                    self.original("repo")?
+                );
+
+                if let Some(reference) = self.original("ref").or(self.original("rev")) {
+                    spec.push('/');
+                    spec.push_str(reference);
+                }
+
+                spec
+            }
+            "indirect" => format!("flake:{}", self.original("id")?),
+            "path" => format!("path:{}", self.original("path")?),
+            "git" | "hg" => format!("{}+{}", kind, self.original("url")?),
+            _ => self.original("url")?.to_string(),
+        };
+
+        let parameters = ["ref", "rev"]
+            .iter()
+            .filter(|_| !matches!(kind, "github" | "gitlab" | "sourcehut"))
+            .filter_map(|name| Some(format!("{}={}", name, self.original(name)?)))
+            .collect::<Vec<String>>();
+
+        if parameters.is_empty() {
+            Some(spec)
+        } else {
+            Some(format!("{}?{}", spec, parameters.join("&")))
+        }
+    }
+
+    /// Where the node comes from and how old its locked revision is
+    pub fn summary(&self, now: i64) -> Option<String> {
+        join_notes([
+            self.forge_label(),
+            self.locked("lastModified")
+                .and_then(|timestamp| describe_timestamp(timestamp, now)),
+        ])
+    }
+
+    pub fn input(&self, name: &str) -> Option<&InputRef> {
+        self.inputs
+            .iter()
+            .find(|(input, _)| input == name)
+            .map(|(_, reference)| reference)
+    }
+}
+
+/// Nodes locked from the same `original` spec at different revisions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateGroup {
+    spec: String,
+    /// Input paths from the root, as accepted by `nix flake update`, and the nodes they lead to
+    inputs: Vec<(String, String)>,
+}
+
+/// Analyses that can be printed with `--report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Report {
+    /// Nodes locked from the same source at different revisions
+    Duplicates,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakeLock {
+    root: String,
+    nodes: Vec<FlakeNode>,
+}
+
+impl FlakeLock {
+    /// Returns `None` if the source doesn't follow the `version`/`root`/`nodes` schema of flake.lock
+    pub fn parse(source: &[u8]) -> anyhow::Result<Option<Self>> {
+        if source.is_empty() {
+            return Ok(None);
+        }
+        let source_location = Location::new(0, arg_context!(source.len().checked_sub(1))?);
+
+        // Only the top level keys are checked first, so other documents are not read as a whole
+        let mut lexer = Lexer::new(source, source_location);
+        let token = arg_context!(lexer.next_token())?;
+        let content = arg_context!(block_content(source, &token))?;
+
+        let (TokenKind::Object, Some(content)) = (token.kind(), content) else {
+            return anyhow::Ok(None);
+        };
+
+        let items = arg_context!(get_object_items(source, content))?;
+        let blanks = arg_context!(items_to_vec(source, &items))?;
+        let is_lock = ["version", "root", "nodes"]
+            .iter()
+            .all(|key| blanks.iter().any(|blank| blank.name() == key));
+
+        if !is_lock {
+            return anyhow::Ok(None);
+        }
+
+        let document = arg_context!(Value::read(source, source_location))?;
+
+        let (Some(Value::Scalar(_)), Some(Value::Scalar(root)), Some(Value::Object(members))) = (
+            document.get("version"),
+            document.get("root"),
+            document.get("nodes"),
+        ) else {
+            return anyhow::Ok(None);
+        };
+
+        let mut nodes = vec![];
+
+        for (name, value) in members.iter() {
+            let mut inputs = vec![];
+
+            if let Some(Value::Object(members)) = value.get("inputs") {
+                for (input, reference) in members.iter() {
+                    let reference = match reference {
+                        Value::Scalar(node) => InputRef::Node(node.clone()),
+                        Value::Array(items) => InputRef::Follows(
+                            items
+                                .iter()
+                                .filter_map(|item| item.as_str().map(str::to_string))
+                                .collect(),
+                        ),
+                        Value::Object(_) => return anyhow::Ok(None),
+                    };
+
+                    inputs.push((input.clone(), reference));
+                }
+            }
+
+            nodes.push(FlakeNode {
+                name: name.clone(),
+                inputs,
+                locked: value.get("locked").map(Value::fields).unwrap_or_default(),
+                original: value.get("original").map(Value::fields).unwrap_or_default(),
+            });
+        }
+
+        anyhow::Ok(Some(Self {
+            root: root.clone(),
+            nodes,
+        }))
+    }
+
+    pub fn node(&self, name: &str) -> Option<&FlakeNode> {
+        self.nodes.iter().find(|node| node.name == name)
+    }
+
+    fn resolve_input_at_depth(
+        &self,
+        node: &str,
+        input: &str,
+        depth: usize,
+    ) -> anyhow::Result<String> {
+        if depth > MAX_FOLLOWS_DEPTH {
+            liab!("The input {:?} of {:?} follows a cycle", input, node);
+        }
+
+        let Some(reference) = self.node(node).and_then(|node| node.input(input)) else {
+            liab!("Node {:?} has no input {:?}", node, input);
+        };
+
+        match reference {
+            InputRef::Node(name) => anyhow::Ok(name.clone()),
+            InputRef::Follows(path) => {
+                let mut current = self.root.clone();
+
+                for name in path.iter() {
+                    current = arg_context!(self.resolve_input_at_depth(&current, name, depth + 1))?;
+                }
+
+                anyhow::Ok(current)
+            }
+        }
+    }
+
+    /// Name of the node the input of a node ends up at, `follows` chains are resolved starting from the root
+    pub fn resolve_input(&self, node: &str, input: &str) -> anyhow::Result<String> {
+        self.resolve_input_at_depth(node, input, 0)
+    }
+
+    /// Node reached by following the input names from the root, like in `nix flake update a/b`
+    pub fn node_at_input_path(&self, input_path: &[&str]) -> anyhow::Result<String> {
+        let mut current = self.root.clone();
+
+        for input in input_path.iter() {
+            current = arg_context!(self.resolve_input(&current, input))?;
+        }
+
+        anyhow::Ok(current)
+    }
+
+    /// Shortest input path from the root to every node that is in use
+    fn input_paths(&self) -> Vec<(String, Vec<String>)> {
+        let mut paths = vec![(self.root.clone(), Vec::<String>::default())];
+        let mut i = 0;
+
+        while let Some((node, path)) = paths.get(i).cloned() {
+            i += 1;
+
+            let Some(flake_node) = self.node(&node) else {
+                continue;
+            };
+
+            for (input, _) in flake_node.inputs.iter() {
+                let Ok(target) = self.resolve_input(&node, input) else {
+                    continue;
+                };
+
+                if !paths.iter().any(|(name, _)| *name == target) {
+                    let mut target_path = path.clone();
+                    target_path.push(input.clone());
+                    paths.push((target, target_path));
+                }
+            }
+        }
+
+        paths
+    }
+
+    /// Nodes that are in use, grouped by their `original` spec, where the group has more than one locked revision
+    pub fn duplicates(&self) -> Vec<DuplicateGroup> {
+        let input_paths = self.input_paths();
+        let mut groups: Vec<(Fields, Vec<&FlakeNode>)> = vec![];
+
+        for node in self.nodes.iter().filter(|node| !node.original.is_empty()) {
+            let mut original = node.original.clone();
+            original.sort();
+
+            match groups.iter_mut().find(|(spec, _)| *spec == original) {
+                Some((_, nodes)) => nodes.push(node),
+                None => groups.push((original, vec![node])),
+            }
+        }
+
+        groups
+            .into_iter()
+            .filter_map(|(original, nodes)| {
+                let mut revs = nodes
+                    .iter()
+                    .filter_map(|node| node.locked_rev())
+                    .collect::<Vec<&str>>();
+                revs.sort();
+                revs.dedup();
+
+                if revs.len() < 2 {
+                    return None;
+                }
+
+                let inputs = input_paths
+                    .iter()
+                    .filter(|(name, _)| nodes.iter().any(|node| node.name == *name))
+                    .map(|(name, path)| (path.join("/"), name.clone()))
+                    .collect::<Vec<(String, String)>>();
+
+                let spec = nodes[0].spec().unwrap_or_else(|| {
+                    original
+                        .iter()
+                        .map(|(name, value)| format!("{}={}", name, value))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                });
+
+                Some(DuplicateGroup { spec, inputs })
+            })
+            .collect()
+    }
+
+    /// JSON document where every group of duplicates holds its input paths with their locked revisions
+    pub fn duplicates_json(&self) -> String {
+        let mut result = String::from("{");
+
+        for (i, group) in self.duplicates().iter().enumerate() {
+            if i > 0 {
+                result.push(',');
+            }
+
+            result.push_str(&json_string(&group.spec));
+            result.push_str(":{");
+
+            for (j, (input_path, node)) in group.inputs.iter().enumerate() {
+                if j > 0 {
+                    result.push(',');
+                }
+
+                let rev = self
+                    .node(node)
+                    .and_then(|node| node.locked_rev())
+                    .unwrap_or_default();
+
+                result.push_str(&json_string(input_path));
+                result.push(':');
+                result.push_str(&json_string(rev));
+            }
+
+            result.push('}');
+        }
+
+        result.push('}');
+        result
+    }
+
+    /// Plain text table of the duplicates, one group per paragraph. It is empty when there are none.
+    pub fn duplicates_report(&self) -> String {
+        let now = now();
+        let mut paragraphs = vec![];
+
+        for group in self.duplicates().iter() {
+            let width = group
+                .inputs
+                .iter()
+                .map(|(input_path, _)| input_path.chars().count())
+                .max()
+                .unwrap_or_default();
+
+            let mut lines = vec![group.spec.clone()];
+
+            for (input_path, node) in group.inputs.iter() {
+                let Some(flake_node) = self.node(node) else {
+                    continue;
+                };
+
+                let rev = flake_node.locked_rev().map(short_rev).unwrap_or_default();
+
+                let date = flake_node
+                    .locked("lastModified")
+                    .and_then(|timestamp| describe_timestamp(timestamp, now))
+                    .unwrap_or_default();
+
+                lines.push(
+                    format!(
+                        "  {:width$}  {:12}  {}",
+                        input_path,
+                        rev,
+                        date,
+                        width = width
+                    )
+                    .trim_end()
+                    .to_string(),
+                );
+            }
+
+            lines.push(String::default());
+            paragraphs.push(lines.join("\n"));
+        }
+
+        paragraphs.join("\n")
+    }
+
+    fn push_graph(
+        &self,
+        node: &str,
+        chain: &mut Vec<String>,
+        result: &mut String,
+    ) -> anyhow::Result<()> {
+        result.push('{');
+
+        // Inputs of a node that is already on the way from the root are not expanded again
+        if let (false, Some(flake_node)) = (chain.iter().any(|name| name == node), self.node(node))
+        {
+            chain.push(node.to_string());
+
+            for (i, (input, _)) in flake_node.inputs.iter().enumerate() {
+                if i > 0 {
+                    result.push(',');
+                }
+
+                result.push_str(&json_string(input));
+                result.push(':');
+
+                let target = arg_context!(self.resolve_input(node, input))?;
+                arg_context!(self.push_graph(&target, chain, result))?;
+            }
+
+            chain.pop();
+        }
+
+        result.push('}');
+
+        anyhow::Ok(())
+    }
+
+    /// JSON document where every input is an object holding the inputs of the node it resolves to
+    pub fn graph_json(&self) -> anyhow::Result<String> {
+        let mut result = String::default();
+        let mut chain = Vec::<String>::default();
+        arg_context!(self.push_graph(&self.root, &mut chain, &mut result))?;
+
+        anyhow::Ok(result)
+    }
+}
+
+/// How far the locked revision moved, for example "b9c03fbbaf84 → aaaaaaaaaaaa, 343 days back"
+fn describe_move(old: &FlakeNode, new: &FlakeNode) -> Option<String> {
+    let (old_rev, new_rev) = (old.locked_rev()?, new.locked_rev()?);
+
+    if old_rev == new_rev {
+        return None;
+    }
+
+    let timestamps = (
+        old.locked("lastModified")
+            .and_then(|timestamp| timestamp.parse::<i64>().ok()),
+        new.locked("lastModified")
+            .and_then(|timestamp| timestamp.parse::<i64>().ok()),
+    );
+
+    let shift = match timestamps {
+        (Some(old), Some(new)) => {
+            let direction = if new > old { "forward" } else { "back" };
+
+            Some(match new.abs_diff(old) / SECONDS_PER_DAY.unsigned_abs() {
+                0 => String::from("same day"),
+                1 => format!("1 day {}", direction),
+                days => format!("{} days {}", days, direction),
+            })
+        }
+        _ => None,
+    };
+
+    join_notes([
+        Some(format!("{} → {}", short_rev(old_rev), short_rev(new_rev))),
+        shift,
+    ])
+}
+
+/// Nodes that were added, removed or locked at another revision, with a description of the change
+pub fn moved_inputs(old: &FlakeLock, new: &FlakeLock) -> Vec<(String, String)> {
+    let mut names = Vec::<&str>::default();
+
+    for node in old.nodes.iter().chain(new.nodes.iter()) {
+        if !names.contains(&node.name.as_str()) {
+            names.push(&node.name);
+        }
+    }
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let description = match (old.node(name), new.node(name)) {
+                (Some(old), Some(new)) => describe_move(old, new)?,
+                (None, Some(new)) => join_notes([Some(String::from("added")), new.summary(now())])?,
+                (Some(_), None) => String::from("removed"),
+                (None, None) => return None,
+            };
+
+            Some((name.to_string(), description))
+        })
+        .collect()
+}
+
+/// Names of the keys in the path, or `None` if there are array items in it
+fn key_names<'e>(entries: &[&'e Entry]) -> Option<Vec<&'e str>> {
+    entries
+        .iter()
+        .map(|entry| match entry.role() {
+            EntryRole::Key => Some(entry.name().as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn describe_reference(reference: &InputRef, target: &str, input: &str) -> Option<String> {
+    match reference {
+        InputRef::Follows(path) => Some(format!("follows {} → {}", path.join("/"), target)),
+        InputRef::Node(_) if target != input => Some(format!("→ {}", target)),
+        InputRef::Node(_) => None,
+    }
+}
+
+/// What the tree is built from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlakeView {
+    /// The flake.lock itself
+    Lock,
+    /// Input dependency graph made by `FlakeLock::graph_json`
+    Graph,
+    /// Groups of duplicate nodes made by `FlakeLock::duplicates_json`
+    Duplicates,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakeMode {
+    lock: FlakeLock,
+    view: FlakeView,
+}
+
+impl FlakeMode {
+    pub fn new(lock: FlakeLock, view: FlakeView) -> Self {
+        Self { lock, view }
+    }
+
+    /// Node the entry under the cursor refers to, the path includes the entry itself.
+    /// In the lock view those are the inputs of the nodes and everything inside them.
+    pub fn reference_target(&self, path: &[&Entry]) -> Option<String> {
+        if self.view != FlakeView::Lock {
+            return None;
+        }
+
+        match key_names(path.get(..4)?)?.as_slice() {
+            ["nodes", node, "inputs", input] => self.lock.resolve_input(node, input).ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether selecting the entry selects its children instead, the path includes the entry itself.
+    /// Groups of duplicates are selected as their input paths.
+    pub fn selects_children(&self, path: &[&Entry]) -> bool {
+        self.view == FlakeView::Duplicates && path.len() == 1
+    }
+
+    /// Short note shown next to the entry, `ancestors` are the entries on the way to it
+    pub fn annotate(&self, ancestors: &[&Entry], entry: &Entry) -> Option<String> {
+        if entry.role() != &EntryRole::Key {
+            return None;
+        }
+
+        let names = key_names(ancestors)?;
+        let name = entry.name().as_str();
+
+        match (self.view, names.as_slice(), name) {
+            (FlakeView::Lock, ["nodes"], node) => self.lock.node(node)?.summary(now()),
+            (FlakeView::Lock, ["nodes", node], "original") => self.lock.node(node)?.forge_label(),
+            (FlakeView::Lock, ["nodes", node], "locked") => {
+                self.lock.node(node)?.locked("rev").map(short_rev)
+            }
+            (FlakeView::Lock, ["nodes", node, "locked"], "lastModified") => {
+                describe_timestamp(self.lock.node(node)?.locked(name)?, now())
+            }
+            (FlakeView::Lock, ["nodes", node, "locked"], "rev") => {
+                Some(short_rev(self.lock.node(node)?.locked(name)?))
+            }
+            (FlakeView::Lock, ["nodes", node, "inputs"], input) => {
+                let reference = self.lock.node(node)?.input(input)?;
+                let target = self.lock.resolve_input(node, input).ok()?;
+
+                describe_reference(reference, &target, input)
+            }
+            (FlakeView::Graph, _, input) => {
+                let node = self.lock.node_at_input_path(&names).ok()?;
+                let reference = self.lock.node(&node)?.input(input)?;
+                let target = self.lock.resolve_input(&node, input).ok()?;
+
+                join_notes([
+                    describe_reference(reference, &target, input),
+                    self.lock.node(&target)?.summary(now()),
+                ])
+            }
+            (FlakeView::Duplicates, [_], input_path) => {
+                let input_path = input_path.split('/').collect::<Vec<&str>>();
+                let node = self.lock.node_at_input_path(&input_path).ok()?;
+                let date = self
+                    .lock
+                    .node(&node)?
+                    .locked("lastModified")
+                    .and_then(|timestamp| describe_timestamp(timestamp, now()));
+
+                join_notes([Some(node), date])
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,53 +1,150 @@
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Position;
 use wrap_context::arg_context;
 
-use crate::{app::App, types::CursorDirection};
+use crate::{app::App, keymap::Action, prompt::PromptKind, types::CursorDirection};
 
 #[rustfmt::skip]
-/// Handles the key events and updates the state of [`App`].
-pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> anyhow::Result<()> {
+/// Handles the key events while the prompt is open.
+fn handle_prompt_key_events(key_event: KeyEvent, app: &mut App) -> anyhow::Result<()> {
     match key_event.code {
-        // Exit application on `ESC` or `q`
-        KeyCode::Esc | KeyCode::Char('q') => {
-            app.quit();
+        KeyCode::Esc => {
+            arg_context!(app.prompt_cancel())?;
         }
-        // Exit application on `Ctrl-C`
         KeyCode::Char('c') | KeyCode::Char('C') if key_event.modifiers == KeyModifiers::CONTROL => {
-            app.quit();
+            arg_context!(app.prompt_cancel())?;
+        }
+        KeyCode::Enter => {
+            arg_context!(app.prompt_submit())?;
         }
-        KeyCode::Char('c') | KeyCode::Backspace => {
-            arg_context!(app.clear_selected())?;
+        KeyCode::Backspace => {
+            arg_context!(app.prompt_backspace())?;
         }
-        KeyCode::Char('A') => {
-            arg_context!(app.select_column())?;
+        KeyCode::Char('u') | KeyCode::Char('U') if key_event.modifiers == KeyModifiers::CONTROL => {
+            arg_context!(app.prompt_clear())?;
         }
-        KeyCode::Down | KeyCode::Char('J') if key_event.modifiers == KeyModifiers::SHIFT => {
-            arg_context!(app.dec_left_table_column_width())?;
+        KeyCode::Char(character) => {
+            arg_context!(app.prompt_input(character))?;
         }
-        KeyCode::Up | KeyCode::Char('K') if key_event.modifiers == KeyModifiers::SHIFT => {
-            arg_context!(app.inc_left_table_column_width())?;
+        _ => {}
+    }
+
+    anyhow::Ok(())
+}
+
+/// Handles the key events while the help is open, the keys of the cursor scroll it.
+/// Page keys have no action of their own, so they are fixed.
+fn handle_help_key_events(key_event: KeyEvent, app: &mut App) -> anyhow::Result<()> {
+    let page = app.help().map_or(1, |help| help.page_height().max(1));
+    let page = isize::try_from(page).unwrap_or(isize::MAX);
+
+    match key_event.code {
+        KeyCode::PageDown => return arg_context!(app.scroll_help(page)),
+        KeyCode::PageUp => return arg_context!(app.scroll_help(-page)),
+        _ => {}
+    }
+
+    let Some(action) = app.key_action(&key_event) else {
+        return anyhow::Ok(());
+    };
+
+    match action {
+        Action::Quit | Action::Help => app.close_help(),
+        Action::ForceQuit => app.quit(),
+        Action::CursorDown => arg_context!(app.scroll_help(1))?,
+        Action::CursorUp => arg_context!(app.scroll_help(-1))?,
+        Action::CursorFirst => arg_context!(app.scroll_help(isize::MIN))?,
+        Action::CursorLast => arg_context!(app.scroll_help(isize::MAX))?,
+        _ => {}
+    }
+
+    anyhow::Ok(())
+}
+
+#[rustfmt::skip]
+/// Handles the key events and updates the state of [`App`].
+/// Keys are looked up in the [`Keymap`](crate::keymap::Keymap) of the app.
+pub fn handle_key_events(key_event: KeyEvent, app: &mut App) -> anyhow::Result<()> {
+    if app.prompt().is_some() {
+        return handle_prompt_key_events(key_event, app);
+    }
+
+    if app.help().is_some() {
+        return handle_help_key_events(key_event, app);
+    }
+
+    let Some(action) = app.key_action(&key_event) else {
+        return anyhow::Ok(());
+    };
+
+    match action {
+        // Unsaved edits have to be confirmed
+        Action::Quit => arg_context!(app.close())?,
+        Action::ForceQuit => app.quit(),
+        Action::SelectEntry => arg_context!(app.select_entry())?,
+        Action::SelectColumn => arg_context!(app.select_column())?,
+        Action::ClearSelected => arg_context!(app.clear_selected())?,
+        Action::CursorDown => arg_context!(app.cursor_move(CursorDirection::Down))?,
+        Action::CursorUp => arg_context!(app.cursor_move(CursorDirection::Up))?,
+        Action::CursorRight => arg_context!(app.cursor_move(CursorDirection::Right))?,
+        Action::CursorLeft => arg_context!(app.cursor_move(CursorDirection::Left))?,
+        Action::CursorFirst => arg_context!(app.cursor_to_end(false))?,
+        Action::CursorLast => arg_context!(app.cursor_to_end(true))?,
+        Action::ResizeLeftInc => arg_context!(app.inc_left_table_column_width())?,
+        Action::ResizeLeftDec => arg_context!(app.dec_left_table_column_width())?,
+        Action::ResizeRightInc => arg_context!(app.inc_rght_table_column_width())?,
+        Action::ResizeRightDec => arg_context!(app.dec_rght_table_column_width())?,
+        Action::Search => arg_context!(app.open_prompt(PromptKind::Search))?,
+        Action::SearchNext => arg_context!(app.search_next())?,
+        Action::SearchPrev => arg_context!(app.search_prev())?,
+        Action::NextError => arg_context!(app.next_error())?,
+        Action::CopyPointer => arg_context!(app.copy_pointer())?,
+        Action::Jump => arg_context!(app.open_prompt(PromptKind::Jump))?,
+        Action::Edit => arg_context!(app.open_editor())?,
+        Action::Rename => arg_context!(app.open_renamer())?,
+        Action::InsertAfter => arg_context!(app.open_inserter(PromptKind::InsertAfter))?,
+        Action::InsertFirst => arg_context!(app.open_inserter(PromptKind::InsertFirst))?,
+        Action::Delete => arg_context!(app.delete_entry())?,
+        Action::Duplicate => arg_context!(app.duplicate_entry())?,
+        Action::Undo => arg_context!(app.undo())?,
+        Action::Write => arg_context!(app.write())?,
+        Action::Reload => arg_context!(app.reload())?,
+        Action::Help => app.open_help(),
+    }
+
+    anyhow::Ok(())
+}
+
+/// Handles the mouse events and updates the state of [`App`].
+pub fn handle_mouse_events(mouse_event: MouseEvent, app: &mut App) -> anyhow::Result<()> {
+    let position = Position::new(mouse_event.column, mouse_event.row);
+
+    if app.help().is_some() {
+        match mouse_event.kind {
+            MouseEventKind::ScrollDown => arg_context!(app.scroll_help(1))?,
+            MouseEventKind::ScrollUp => arg_context!(app.scroll_help(-1))?,
+            _ => {}
+        }
+
+        return anyhow::Ok(());
+    }
+
+    match mouse_event.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            arg_context!(app.mouse_down(position))?;
         }
-        KeyCode::Down | KeyCode::Char('j') if key_event.modifiers == KeyModifiers::CONTROL => {
-            arg_context!(app.dec_rght_table_column_width())?;
+        MouseEventKind::Drag(MouseButton::Left) => {
+            arg_context!(app.mouse_drag(position))?;
         }
-        KeyCode::Up | KeyCode::Char('k') if key_event.modifiers == KeyModifiers::CONTROL => {
-            arg_context!(app.inc_rght_table_column_width())?;
+        MouseEventKind::Up(MouseButton::Left) => {
+            arg_context!(app.mouse_up())?;
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            arg_context!(app.cursor_move(CursorDirection::Down))?
+        MouseEventKind::ScrollDown => {
+            arg_context!(app.mouse_scroll(position, CursorDirection::Down))?;
         }
-        KeyCode::Up | KeyCode::Char('k') => {
-            arg_context!(app.cursor_move(CursorDirection::Up))?
-        },
-        KeyCode::Right | KeyCode::Char('l') => {
-            arg_context!(app.cursor_move(CursorDirection::Right))?
+        MouseEventKind::ScrollUp => {
+            arg_context!(app.mouse_scroll(position, CursorDirection::Up))?;
         }
-        KeyCode::Left | KeyCode::Char('h') => {
-            arg_context!(app.cursor_move(CursorDirection::Left))?
-        },
-        KeyCode::Enter | KeyCode::Char(' ') => {
-            arg_context!(app.select_entry())?
-        },
         _ => {}
     }
 
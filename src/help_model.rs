@@ -0,0 +1,61 @@
+use impl_helper::ImplHelper;
+use ratatui::layout::Rect;
+
+/// Popup with the keys of every action
+#[derive(Default, Debug, Clone, PartialEq, Eq, ImplHelper)]
+pub struct HelpModel {
+    #[helper(all)]
+    area: Rect,
+
+    /// Keys and description of each action
+    #[helper(all)]
+    lines: Vec<(String, String)>,
+
+    /// Index of the first line shown
+    #[helper(get)]
+    offset: usize,
+}
+
+impl HelpModel {
+    /// Number of lines that fit between the borders
+    pub fn page_height(&self) -> usize {
+        usize::from(self.area.height.saturating_sub(2))
+    }
+
+    /// Moves the lines by the delta, without scrolling past the last page
+    pub fn scroll(&mut self, delta: isize) {
+        let last_offset = self.lines.len().saturating_sub(self.page_height());
+        self.offset = self.offset.saturating_add_signed(delta).min(last_offset);
+    }
+
+    /// Centered area that fits all the lines, clipped to the given one
+    pub fn centered_area(&self, outer: Rect) -> Rect {
+        let keys_width = self
+            .lines
+            .iter()
+            .map(|(keys, _)| keys.chars().count())
+            .max()
+            .unwrap_or_default();
+        let description_width = self
+            .lines
+            .iter()
+            .map(|(_, description)| description.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        // Borders and the gap between the columns
+        let width = u16::try_from(keys_width + description_width + 4)
+            .unwrap_or(u16::MAX)
+            .min(outer.width);
+        let height = u16::try_from(self.lines.len() + 2)
+            .unwrap_or(u16::MAX)
+            .min(outer.height);
+
+        Rect::new(
+            outer.x + (outer.width - width) / 2,
+            outer.y + (outer.height - height) / 2,
+            width,
+            height,
+        )
+    }
+}
@@ -0,0 +1,66 @@
+use itertools::Itertools;
+use ratatui::{
+    layout::Rect,
+    style::Stylize,
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{help_model::HelpModel, render::Render};
+
+#[derive(Debug, Default)]
+pub struct HelpView<'a> {
+    area: Rect,
+    paragraph: Paragraph<'a>,
+}
+
+impl<'a> From<&HelpModel> for HelpView<'a> {
+    fn from(model: &HelpModel) -> Self {
+        let area = *model.area();
+
+        let keys_width = model
+            .lines()
+            .iter()
+            .map(|(keys, _)| keys.chars().count())
+            .max()
+            .unwrap_or_default();
+
+        let lines = model
+            .lines()
+            .iter()
+            .skip(*model.offset())
+            .take(model.page_height())
+            .map(|(keys, description)| {
+                Line::from(vec![
+                    Span::from(format!("{:width$}", keys, width = keys_width)).bold(),
+                    Span::from(" "),
+                    Span::from(description.clone()),
+                ])
+            })
+            .collect_vec();
+
+        let last_line = (model.offset() + model.page_height()).min(model.lines().len());
+        let position = format!(
+            " {}-{}/{} ",
+            model.offset() + 1,
+            last_line,
+            model.lines().len()
+        );
+
+        let block = Block::bordered()
+            .title(" Keys ")
+            .title_bottom(Line::from(position).right_aligned().dim());
+
+        let paragraph = Paragraph::new(lines).block(block);
+
+        Self { area, paragraph }
+    }
+}
+
+impl<'a> Render for HelpView<'a> {
+    fn render(&mut self, frame: &mut Frame) {
+        frame.render_widget(Clear, self.area);
+        frame.render_widget(&self.paragraph, self.area);
+    }
+}
@@ -0,0 +1,74 @@
+use memmap2::Mmap;
+use std::{
+    fs::File,
+    io::{stdin, IsTerminal, Read},
+    path::Path,
+};
+use wrap_context::{arg_context, liab};
+
+pub const STDIN_NAME: &str = "<stdin>";
+
+/// Regular files are memory-mapped, everything else (stdin, pipes, FIFOs) is read into a buffer
+#[derive(Debug)]
+pub enum Input {
+    Mapped(Mmap),
+    Buffered(Vec<u8>),
+}
+
+fn is_stdin(file: &Path) -> bool {
+    file == Path::new("-")
+}
+
+impl Input {
+    pub fn read(file: Option<&Path>) -> anyhow::Result<Self> {
+        let mut buffer = vec![];
+
+        match file {
+            Some(file) if !is_stdin(file) => {
+                let mut file = arg_context!(File::open(file))?;
+
+                if arg_context!(file.metadata())?.is_file() {
+                    let mmap = unsafe { arg_context!(Mmap::map(&file))? };
+                    return anyhow::Ok(Self::Mapped(mmap));
+                }
+
+                arg_context!(file.read_to_end(&mut buffer))?;
+            }
+            _ => {
+                let mut stdin = stdin().lock();
+
+                if stdin.is_terminal() {
+                    liab!("No input provided: pass a FILE or pipe the data to stdin");
+                }
+
+                arg_context!(stdin.read_to_end(&mut buffer))?;
+            }
+        }
+
+        anyhow::Ok(Self::Buffered(buffer))
+    }
+
+    /// Reads the whole file into memory, so it can be replaced or truncated afterwards
+    pub fn read_buffered(file: &Path) -> anyhow::Result<Self> {
+        let mut buffer = vec![];
+        let mut file = arg_context!(File::open(file))?;
+        arg_context!(file.read_to_end(&mut buffer))?;
+
+        anyhow::Ok(Self::Buffered(buffer))
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Input::Mapped(mmap) => &mmap[..],
+            Input::Buffered(buffer) => &buffer[..],
+        }
+    }
+}
+
+/// Name of the input shown to the user
+pub fn input_name(file: Option<&Path>) -> String {
+    match file {
+        Some(file) if !is_stdin(file) => file.display().to_string(),
+        _ => STDIN_NAME.to_string(),
+    }
+}
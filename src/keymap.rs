@@ -0,0 +1,490 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use itertools::Itertools;
+use std::fmt;
+use wrap_context::{arg_context, liab};
+
+use crate::config::ConfigValue;
+
+/// Everything that can be done with a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ForceQuit,
+    SelectEntry,
+    SelectColumn,
+    ClearSelected,
+    CursorDown,
+    CursorUp,
+    CursorRight,
+    CursorLeft,
+    CursorFirst,
+    CursorLast,
+    ResizeLeftInc,
+    ResizeLeftDec,
+    ResizeRightInc,
+    ResizeRightDec,
+    Search,
+    SearchNext,
+    SearchPrev,
+    NextError,
+    CopyPointer,
+    Jump,
+    Edit,
+    Rename,
+    InsertAfter,
+    InsertFirst,
+    Delete,
+    Duplicate,
+    Undo,
+    Write,
+    Reload,
+    Help,
+}
+
+impl Action {
+    /// All actions, in the order they are listed in the help
+    pub const ALL: &'static [Action] = &[
+        Action::Quit,
+        Action::ForceQuit,
+        Action::SelectEntry,
+        Action::SelectColumn,
+        Action::ClearSelected,
+        Action::CursorDown,
+        Action::CursorUp,
+        Action::CursorRight,
+        Action::CursorLeft,
+        Action::CursorFirst,
+        Action::CursorLast,
+        Action::ResizeLeftInc,
+        Action::ResizeLeftDec,
+        Action::ResizeRightInc,
+        Action::ResizeRightDec,
+        Action::Search,
+        Action::SearchNext,
+        Action::SearchPrev,
+        Action::NextError,
+        Action::CopyPointer,
+        Action::Jump,
+        Action::Edit,
+        Action::Rename,
+        Action::InsertAfter,
+        Action::InsertFirst,
+        Action::Delete,
+        Action::Duplicate,
+        Action::Undo,
+        Action::Write,
+        Action::Reload,
+        Action::Help,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit, asks once more if there are unsaved edits",
+            Action::ForceQuit => "Quit right away",
+            Action::SelectEntry => "Select entry",
+            Action::SelectColumn => "Select all entries in the column",
+            Action::ClearSelected => "Clear all selected entries",
+            Action::CursorDown => "Move cursor down",
+            Action::CursorUp => "Move cursor up",
+            Action::CursorRight => "Move cursor right",
+            Action::CursorLeft => "Move cursor left",
+            Action::CursorFirst => "Move cursor to the first entry of the column",
+            Action::CursorLast => "Move cursor to the last entry of the column",
+            Action::ResizeLeftInc => "Widen left table",
+            Action::ResizeLeftDec => "Narrow left table",
+            Action::ResizeRightInc => "Widen right table",
+            Action::ResizeRightDec => "Narrow right table",
+            Action::Search => "Search keys and values",
+            Action::SearchNext => "Jump to next match",
+            Action::SearchPrev => "Jump to previous match",
+            Action::NextError => "Jump to the next part that could not be read",
+            Action::CopyPointer => "Copy JSON Pointer to clipboard",
+            Action::Jump => "Jump to a path",
+            Action::Edit => "Edit the value under the cursor",
+            Action::Rename => "Rename the key under the cursor",
+            Action::InsertAfter => "Insert a member after the cursor",
+            Action::InsertFirst => "Insert a member into the object or array under the cursor",
+            Action::Delete => "Delete the entry under the cursor",
+            Action::Duplicate => "Duplicate the entry under the cursor",
+            Action::Undo => "Undo the last edit",
+            Action::Write => "Write the edits to the file",
+            Action::Reload => "Read the file again, dropping unsaved edits",
+            Action::Help => "Show this help",
+        }
+    }
+
+    /// Name used in the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::ForceQuit => "force_quit",
+            Action::SelectEntry => "select_entry",
+            Action::SelectColumn => "select_column",
+            Action::ClearSelected => "clear_selected",
+            Action::CursorDown => "cursor_down",
+            Action::CursorUp => "cursor_up",
+            Action::CursorRight => "cursor_right",
+            Action::CursorLeft => "cursor_left",
+            Action::CursorFirst => "cursor_first",
+            Action::CursorLast => "cursor_last",
+            Action::ResizeLeftInc => "resize_left_inc",
+            Action::ResizeLeftDec => "resize_left_dec",
+            Action::ResizeRightInc => "resize_right_inc",
+            Action::ResizeRightDec => "resize_right_dec",
+            Action::Search => "search",
+            Action::SearchNext => "search_next",
+            Action::SearchPrev => "search_prev",
+            Action::NextError => "next_error",
+            Action::CopyPointer => "copy_pointer",
+            Action::Jump => "jump",
+            Action::Edit => "edit",
+            Action::Rename => "rename",
+            Action::InsertAfter => "insert_after",
+            Action::InsertFirst => "insert_first",
+            Action::Delete => "delete",
+            Action::Duplicate => "duplicate",
+            Action::Undo => "undo",
+            Action::Write => "write",
+            Action::Reload => "reload",
+            Action::Help => "help",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+}
+
+/// Key with its modifiers, in the form the bindings are compared in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Terminals differ in the Shift and case they send with characters
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(character) if modifiers.contains(KeyModifiers::CONTROL) => Self {
+                code: KeyCode::Char(character.to_ascii_lowercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            KeyCode::Char(_) => Self {
+                code,
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            _ => Self { code, modifiers },
+        }
+    }
+
+    /// Reads a chord written like `j`, `J`, `ctrl+j`, `shift+down` or `space`
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let (modifier_names, key) = match text.strip_suffix("++") {
+            Some(modifier_names) => (modifier_names, "+"),
+            None => match text.rsplit_once('+') {
+                Some((modifier_names, key)) if !key.is_empty() => (modifier_names, key),
+                _ => ("", text),
+            },
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+
+        for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+            modifiers |= match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => liab!(
+                    "unknown modifier {:?} in {:?}, use ctrl, alt or shift",
+                    name,
+                    text
+                ),
+            };
+        }
+
+        let code = match key.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "tab" => KeyCode::Tab,
+            "down" => KeyCode::Down,
+            "up" => KeyCode::Up,
+            "right" => KeyCode::Right,
+            "left" => KeyCode::Left,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            name => match (
+                name.strip_prefix('f').map(str::parse::<u8>),
+                key.chars().exactly_one(),
+            ) {
+                (Some(Ok(number)), _) if (1..=12).contains(&number) => KeyCode::F(number),
+                (_, Ok(character)) => KeyCode::Char(character),
+                _ => liab!("unknown key {:?}", text),
+            },
+        };
+
+        anyhow::Ok(Self::new(code, modifiers))
+    }
+
+    /// Whether the chord is a character typed without Ctrl or Alt
+    fn is_plain_char(&self) -> bool {
+        matches!(self.code, KeyCode::Char(character) if character != ' ')
+            && self.modifiers.is_empty()
+    }
+
+    fn key(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    fn char(character: char) -> Self {
+        Self::key(KeyCode::Char(character))
+    }
+
+    fn ctrl(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::CONTROL)
+    }
+
+    fn shift(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::SHIFT)
+    }
+}
+
+impl From<&KeyEvent> for KeyChord {
+    fn from(event: &KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}", name)?;
+            }
+        }
+
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(character) => write!(f, "{}", character),
+            KeyCode::Down => write!(f, "↓"),
+            KeyCode::Up => write!(f, "↑"),
+            KeyCode::Right => write!(f, "→"),
+            KeyCode::Left => write!(f, "←"),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Tab => write!(f, "Tab"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PageUp"),
+            KeyCode::PageDown => write!(f, "PageDown"),
+            KeyCode::Delete => write!(f, "Delete"),
+            KeyCode::Insert => write!(f, "Insert"),
+            KeyCode::F(number) => write!(f, "F{}", number),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Chords pressed one after another, written like `gg`, `g g` or `ctrl+w j`
+pub fn parse_sequence(text: &str) -> anyhow::Result<Vec<KeyChord>> {
+    let mut sequence = vec![];
+
+    for word in text.split_whitespace() {
+        match KeyChord::parse(word) {
+            Ok(chord) => sequence.push(chord),
+            // Words like `gg` are a character for each chord
+            Err(_) if !word.contains('+') => {
+                sequence.extend(word.chars().map(KeyChord::char));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    if sequence.is_empty() {
+        liab!("the keys can't be empty");
+    }
+
+    anyhow::Ok(sequence)
+}
+
+fn sequence_to_string(sequence: &[KeyChord]) -> String {
+    if sequence.iter().all(KeyChord::is_plain_char) {
+        sequence.iter().join("")
+    } else {
+        sequence.iter().join(" ")
+    }
+}
+
+/// Whether one of the sequences starts with the other, so both can't be bound at once
+fn overlap(a: &[KeyChord], b: &[KeyChord]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// What the keys pressed so far lead to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMatch {
+    Action(Action),
+    /// The keys are the start of a longer sequence
+    Pending,
+    None,
+}
+
+/// Keys of every action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(Vec<KeyChord>, Action)>,
+    /// Sequences set in the config file, they replace the default bindings they overlap with
+    configured: Vec<Vec<KeyChord>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Action::*;
+
+        let bindings = vec![
+            (vec![KeyChord::char('q')], Quit),
+            (vec![KeyChord::key(KeyCode::Esc)], Quit),
+            (vec![KeyChord::ctrl(KeyCode::Char('c'))], ForceQuit),
+            (vec![KeyChord::key(KeyCode::Enter)], SelectEntry),
+            (vec![KeyChord::char(' ')], SelectEntry),
+            (vec![KeyChord::char('A')], SelectColumn),
+            (vec![KeyChord::char('c')], ClearSelected),
+            (vec![KeyChord::key(KeyCode::Backspace)], ClearSelected),
+            (vec![KeyChord::key(KeyCode::Down)], CursorDown),
+            (vec![KeyChord::char('j')], CursorDown),
+            (vec![KeyChord::key(KeyCode::Up)], CursorUp),
+            (vec![KeyChord::char('k')], CursorUp),
+            (vec![KeyChord::key(KeyCode::Right)], CursorRight),
+            (vec![KeyChord::char('l')], CursorRight),
+            (vec![KeyChord::key(KeyCode::Left)], CursorLeft),
+            (vec![KeyChord::char('h')], CursorLeft),
+            (vec![KeyChord::char('g'), KeyChord::char('g')], CursorFirst),
+            (vec![KeyChord::key(KeyCode::Home)], CursorFirst),
+            (vec![KeyChord::char('G')], CursorLast),
+            (vec![KeyChord::key(KeyCode::End)], CursorLast),
+            (vec![KeyChord::shift(KeyCode::Up)], ResizeLeftInc),
+            (vec![KeyChord::char('K')], ResizeLeftInc),
+            (vec![KeyChord::shift(KeyCode::Down)], ResizeLeftDec),
+            (vec![KeyChord::char('J')], ResizeLeftDec),
+            (vec![KeyChord::ctrl(KeyCode::Up)], ResizeRightInc),
+            (vec![KeyChord::ctrl(KeyCode::Char('k'))], ResizeRightInc),
+            (vec![KeyChord::ctrl(KeyCode::Down)], ResizeRightDec),
+            (vec![KeyChord::ctrl(KeyCode::Char('j'))], ResizeRightDec),
+            (vec![KeyChord::char('/')], Search),
+            (vec![KeyChord::char('n')], SearchNext),
+            (vec![KeyChord::char('N')], SearchPrev),
+            (vec![KeyChord::char('E')], NextError),
+            (vec![KeyChord::char('y')], CopyPointer),
+            (vec![KeyChord::char(':')], Jump),
+            (vec![KeyChord::char('e')], Edit),
+            (vec![KeyChord::char('r')], Rename),
+            (vec![KeyChord::char('i')], InsertAfter),
+            (vec![KeyChord::char('I')], InsertFirst),
+            (vec![KeyChord::char('d')], Delete),
+            (vec![KeyChord::char('D')], Duplicate),
+            (vec![KeyChord::char('u')], Undo),
+            (vec![KeyChord::char('w')], Write),
+            (vec![KeyChord::char('R')], Reload),
+            (vec![KeyChord::char('?')], Help),
+        ];
+
+        Self {
+            bindings,
+            configured: vec![],
+        }
+    }
+}
+
+impl Keymap {
+    pub fn lookup(&self, keys: &[KeyChord]) -> KeyMatch {
+        if let Some((_, action)) = self.bindings.iter().find(|(sequence, _)| sequence == keys) {
+            return KeyMatch::Action(*action);
+        }
+
+        if self
+            .bindings
+            .iter()
+            .any(|(sequence, _)| sequence.starts_with(keys))
+        {
+            return KeyMatch::Pending;
+        }
+
+        KeyMatch::None
+    }
+
+    /// Binds the keys to the action named by the value, `"none"` only removes the default bindings of the keys
+    pub fn bind(&mut self, keys: &str, value: &ConfigValue) -> anyhow::Result<()> {
+        let sequence = arg_context!(parse_sequence(keys))?;
+
+        let action = match value {
+            ConfigValue::String(name) if name == "none" => None,
+            ConfigValue::String(name) => match Action::from_name(name) {
+                Some(action) => Some(action),
+                None => liab!(
+                    "unknown action {:?}, the actions are: {}",
+                    name,
+                    Action::ALL.iter().map(Action::name).join(", ")
+                ),
+            },
+            value => liab!("expected the name of an action, got {}", value.kind()),
+        };
+
+        if let Some(earlier) = self
+            .configured
+            .iter()
+            .find(|earlier| overlap(earlier, &sequence))
+        {
+            if *earlier == sequence {
+                liab!("{:?} is already bound", sequence_to_string(&sequence));
+            }
+
+            liab!(
+                "{:?} and {:?} can't both be bound, one starts with the other",
+                sequence_to_string(&sequence),
+                sequence_to_string(earlier)
+            );
+        }
+
+        self.bindings
+            .retain(|(bound, _)| !overlap(bound, &sequence));
+
+        if let Some(action) = action {
+            self.bindings.push((sequence.clone(), action));
+        }
+
+        self.configured.push(sequence);
+
+        anyhow::Ok(())
+    }
+
+    /// Keys and description of every action that has keys, one line each
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        Action::ALL
+            .iter()
+            .filter_map(|action| {
+                let keys = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, bound)| bound == action)
+                    .map(|(sequence, _)| sequence_to_string(sequence))
+                    .collect::<Vec<_>>();
+
+                (!keys.is_empty()).then(|| (keys.join(" / "), action.description()))
+            })
+            .collect()
+    }
+}
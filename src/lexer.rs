@@ -1,86 +1,270 @@
 use std::{collections::VecDeque, str::from_utf8};
 
 use impl_helper::ImplHelper;
-use memchr::{memchr, memchr_iter};
+use memchr::{memchr, memmem};
 use wrap_context::{arg_context, liab, raw_context};
 
-use crate::utils::{Location, SliceFromLocation};
+use crate::{
+    diagnostic::Diagnostic,
+    utils::{clip_string, Location, SliceFromLocation},
+};
+
+/// Position of the quote that closes the string opened at `start`
+fn string_finish(source: &[u8], start: usize, finish: usize, quote: u8) -> Option<usize> {
+    let mut search_start = start + 1;
+
+    while search_start <= finish {
+        let position = search_start + memchr(quote, &source[search_start..=finish])?;
+        let backslashes = source[..position]
+            .iter()
+            .rev()
+            .take_while(|byte| **byte == b'\\')
+            .count();
+
+        if backslashes % 2 == 0 {
+            return Some(position);
+        }
 
-fn count_quotes(haystack: &[u8]) -> anyhow::Result<usize> {
-    let mut it = memchr_iter(b'"', haystack);
-    let mut quote_counter = 0;
+        search_start = position + 1;
+    }
 
-    while let Some(mut i) = it.next() {
-        let mut backslash_counter = 0;
+    None
+}
 
-        while i > 0 {
-            i -= 1;
+/// Location of the comment that starts at the position, an unclosed `/*` one reaches the finish
+fn comment_at(source: &[u8], position: usize, finish: usize) -> Option<Location> {
+    let rest = source.get(position..=finish)?;
 
-            if arg_context!(haystack.get(i))? == &b'\\' {
-                backslash_counter += 1;
-            } else {
-                break;
-            }
-        }
+    if rest.starts_with(b"//") {
+        let end = memchr(b'\n', rest).map_or(finish, |newline| position + newline - 1);
+        return Some(Location::new(position, end.max(position + 1)));
+    }
 
-        if backslash_counter % 2 == 0 {
-            quote_counter += 1;
+    if rest.starts_with(b"/*") {
+        let end = memmem::find(&rest[2..], b"*/").map_or(finish, |close| position + close + 3);
+        return Some(Location::new(position, end));
+    }
+
+    None
+}
+
+/// Positions of the bytes outside of strings and comments.
+/// Single quotes only open a string at the start of a token, so bare words such as `don't` stay whole.
+pub struct Structure<'a> {
+    source: &'a [u8],
+    pos: usize,
+    finish: usize,
+    token_start: bool,
+    /// Comments passed so far
+    comments: Vec<Location>,
+}
+
+impl<'a> Structure<'a> {
+    pub fn new(source: &'a [u8], location: Location) -> Self {
+        Self {
+            source,
+            pos: *location.start(),
+            finish: *location.finish().min(&source.len().saturating_sub(1)),
+            token_start: true,
+            comments: vec![],
         }
     }
 
-    anyhow::Ok(quote_counter)
+    pub fn comments(&self) -> &[Location] {
+        &self.comments
+    }
 }
 
-fn count_needles(needle: u8, haystack: &[u8], quotes: &mut usize) -> anyhow::Result<usize> {
-    if let Some(position) = memchr(needle, haystack) {
-        *quotes += arg_context!(count_quotes(&haystack[..position]))?;
+impl Iterator for Structure<'_> {
+    type Item = usize;
 
-        if arg_context!(position.checked_add(1))? >= haystack.len() {
-            if *quotes % 2 == 0 {
-                return anyhow::Ok(1);
-            } else {
-                return anyhow::Ok(0);
+    fn next(&mut self) -> Option<usize> {
+        while self.pos <= self.finish {
+            let position = self.pos;
+            let byte = self.source[position];
+
+            if let Some(comment) = comment_at(self.source, position, self.finish) {
+                self.comments.push(comment);
+                self.pos = comment.finish() + 1;
+                self.token_start = true;
+                continue;
             }
-        } else {
-            if *quotes % 2 == 0 {
-                return anyhow::Ok(
-                    1 + raw_context!(count_needles(
-                        needle,
-                        &haystack[arg_context!(position.checked_add(1))?..],
-                        quotes,
-                    ))?,
-                );
-            } else {
-                return anyhow::Ok(
-                    0 + raw_context!(count_needles(
-                        needle,
-                        &haystack[arg_context!(position.checked_add(1))?..],
-                        quotes,
-                    ))?,
-                );
+
+            if byte == b'"' || (byte == b'\'' && self.token_start) {
+                // A string that is not closed takes the rest
+                self.pos = string_finish(self.source, position, self.finish, byte)
+                    .map_or(self.finish + 1, |close| close + 1);
+                self.token_start = false;
+                continue;
             }
+
+            self.pos += 1;
+            self.token_start = byte.is_ascii_whitespace() || b"{}[],:".contains(&byte);
+
+            return Some(position);
         }
-    } else {
-        *quotes += arg_context!(count_quotes(&haystack))?;
 
-        return anyhow::Ok(0);
+        None
     }
 }
 
-pub fn row_col_position(source: &[u8]) -> anyhow::Result<String> {
-    let mut it = memchr_iter(b'\n', source);
+/// Comments in the location joined on one line, `None` if there are none
+pub fn comments_in(source: &[u8], location: Location) -> Option<String> {
+    let mut structure = Structure::new(source, location);
+    structure.by_ref().for_each(drop);
+
+    let text = structure
+        .comments()
+        .iter()
+        .map(|comment| {
+            let text = source.slice(comment);
+            let text = match text.starts_with(b"/*") {
+                true => text[2..].strip_suffix(b"*/").unwrap_or(&text[2..]),
+                false => &text[2..],
+            };
 
-    let mut row = 1; // because rows start from 1
+            String::from_utf8_lossy(text)
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ");
 
-    let mut last_newline_position = 0;
-    while let Some(i) = it.next() {
-        row += 1;
-        last_newline_position = i + 1; // + 1 because columns start from 1
+    (!text.is_empty()).then_some(text)
+}
+
+fn read_code_unit(source: &[u8], location: &Location, offset: usize) -> anyhow::Result<u32> {
+    let raw = source.slice(location);
+
+    if let Some(hex) = offset
+        .checked_add(4)
+        .and_then(|finish| raw.get(offset..finish))
+    {
+        if hex.iter().all(u8::is_ascii_hexdigit) {
+            let hex = arg_context!(from_utf8(hex))?;
+            return anyhow::Ok(arg_context!(u32::from_str_radix(hex, 16))?);
+        }
     }
 
-    let col = source.len() - last_newline_position;
+    // The first character that is not a digit, which may be the closing quote
+    let digits = raw
+        .iter()
+        .skip(offset)
+        .take(4)
+        .take_while(|byte| byte.is_ascii_hexdigit())
+        .count();
+    let position = location.start() + offset + digits;
+
+    Err(Diagnostic::new(
+        source,
+        position,
+        String::from("four hex digits after `\\u`"),
+        Diagnostic::describe(source, position),
+    )
+    .into())
+}
+
+/// Decodes escape sequences of the string inside the given location (without the quotes)
+pub fn decode_string(source: &[u8], location: &Location) -> anyhow::Result<String> {
+    let raw = source.slice(location);
 
-    anyhow::Ok(format!("{row}:{col}"))
+    if memchr(b'\\', raw).is_none() {
+        return anyhow::Ok(arg_context!(from_utf8(raw))?.to_string());
+    }
+
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+
+    while let Some(byte) = raw.get(i) {
+        if *byte != b'\\' {
+            bytes.push(*byte);
+            i += 1;
+            continue;
+        }
+
+        let escape_position = location.start() + i;
+
+        let decoded = match raw.get(i + 1) {
+            Some(b'"') => '"',
+            Some(b'\'') => '\'',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                let code_unit = arg_context!(read_code_unit(source, location, i + 2))?;
+
+                let code_point = match code_unit {
+                    0xD800..=0xDBFF => {
+                        if raw.get(i + 6) != Some(&b'\\') || raw.get(i + 7) != Some(&b'u') {
+                            return Err(Diagnostic::new(
+                                source,
+                                escape_position + 6,
+                                format!("a low surrogate after `\\u{:04X}`", code_unit),
+                                Diagnostic::describe(source, escape_position + 6),
+                            )
+                            .into());
+                        }
+
+                        let low_code_unit = arg_context!(read_code_unit(source, location, i + 8))?;
+                        if !(0xDC00..=0xDFFF).contains(&low_code_unit) {
+                            return Err(Diagnostic::new(
+                                source,
+                                escape_position + 6,
+                                format!("a low surrogate after `\\u{:04X}`", code_unit),
+                                format!("`\\u{:04X}`", low_code_unit),
+                            )
+                            .into());
+                        }
+
+                        // The second escape sequence is consumed here, the first one below
+                        i += 6;
+
+                        0x10000 + ((code_unit - 0xD800) << 10) + (low_code_unit - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => {
+                        return Err(Diagnostic::new(
+                            source,
+                            escape_position,
+                            String::from("a high surrogate before the low one"),
+                            format!("`\\u{:04X}` on its own", code_unit),
+                        )
+                        .into())
+                    }
+                    _ => code_unit,
+                };
+
+                i += 4;
+
+                arg_context!(char::from_u32(code_point))?
+            }
+            other => {
+                let found = match other {
+                    Some(other) => format!("`\\{}`", char::from(*other)),
+                    None => String::from("`\\` at the end of the string"),
+                };
+
+                return Err(Diagnostic::new(
+                    source,
+                    escape_position,
+                    String::from("an escape sequence such as `\\n`, `\\\"` or `\\u00e9`"),
+                    found,
+                )
+                .into());
+            }
+        };
+
+        let mut buffer = [0; 4];
+        bytes.extend_from_slice(decoded.encode_utf8(&mut buffer).as_bytes());
+        i += 2;
+    }
+
+    anyhow::Ok(raw_context!(String::from_utf8(bytes))?)
 }
 
 fn find_block(
@@ -90,54 +274,46 @@ fn find_block(
     ending: u8,
 ) -> anyhow::Result<Location> {
     let start = state.pos().clone();
+    let mut opennings = 0usize;
 
-    let mut search_start = arg_context!(start.checked_add(1))?;
-    if search_start > *data.location().finish() {
-        liab!(
-            "Could not find '{}' for '{}' at [{}]",
-            from_utf8(&[ending])?.to_string(),
-            from_utf8(&[openning])?.to_string(),
-            row_col_position(&data.source()[..=start])?,
-        );
-    }
+    let location = Location::new(start, *data.location().finish());
 
-    let mut quotes = 0;
-    let mut opennings = 1;
+    for position in Structure::new(data.source(), location) {
+        match data.source()[position] {
+            byte if byte == openning => opennings += 1,
+            byte if byte == ending => {
+                opennings -= 1;
 
-    loop {
-        if let Some(position) = memchr(
-            ending,
-            &data.source()[search_start..=*data.location().finish()],
-        ) {
-            let finish = search_start + position;
-
-            opennings +=
-                count_needles(openning, &data.source()[search_start..finish], &mut quotes)?;
-
-            if quotes % 2 != 0 {
-                search_start = arg_context!(finish.checked_add(1))?;
-                continue;
-            }
+                if opennings == 0 {
+                    state.pos_update(position);
+                    state.byt_update(Some(ending));
 
-            opennings -= 1;
-
-            if opennings == 0 {
-                state.pos_update(finish);
-                state.byt_update(Some(ending));
-
-                return anyhow::Ok(Location::new(start, finish));
-            } else {
-                search_start = arg_context!(finish.checked_add(1))?;
+                    return anyhow::Ok(Location::new(start, position));
+                }
             }
-        } else {
-            liab!(
-                "Could not find '{}' for '{}' at [{}]",
-                from_utf8(&[ending])?.to_string(),
-                from_utf8(&[openning])?.to_string(),
-                row_col_position(&data.source()[..=start])?,
-            );
+            _ => {}
         }
     }
+
+    Err(unclosed(data, start, openning, ending).into())
+}
+
+/// Diagnostic for a bracket or a quote at the start that is never closed
+fn unclosed(data: &LexerData, start: usize, openning: u8, ending: u8) -> Diagnostic {
+    // The data may be the inside of a block, which ends right before its closing bracket
+    let end = data.location().finish() + 1;
+    let hint = match openning {
+        b'"' | b'\'' => String::from("unclosed string"),
+        _ => format!("unclosed `{}`", char::from(openning)),
+    };
+
+    Diagnostic::new(
+        data.source(),
+        end,
+        format!("`{}`", char::from(ending)),
+        Diagnostic::describe(data.source(), end),
+    )
+    .related_set(data.source(), start, &hint)
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -148,7 +324,135 @@ pub enum TokenKind {
     Object,
     Array,
     String,
-    Sequence,
+    Number,
+    Boolean,
+    Null,
+    BareWord,
+    /// Part of the source that could not be read, only made when reading goes on after mistakes
+    Error,
+}
+
+impl TokenKind {
+    fn description(&self) -> &'static str {
+        match self {
+            TokenKind::LastChar => "the end",
+            TokenKind::Comma => "`,`",
+            TokenKind::Colon => "`:`",
+            TokenKind::Object => "an object",
+            TokenKind::Array => "an array",
+            TokenKind::String => "a string",
+            TokenKind::Number => "a number",
+            TokenKind::Boolean => "a boolean",
+            TokenKind::Null => "`null`",
+            TokenKind::BareWord => "a bare word",
+            TokenKind::Error => "a broken value",
+        }
+    }
+}
+
+/// What the kinds are called in diagnostics, `end` describes what follows the data
+pub fn describe_kinds(kinds: &[TokenKind], end: &str) -> String {
+    let mut descriptions = vec![];
+    let mut rest = kinds.to_vec();
+
+    for (group, description) in [(VALUE_KINDS, "a value"), (KEY_KINDS, "a key")] {
+        if group.iter().all(|kind| rest.contains(kind)) {
+            rest.retain(|kind| !group.contains(kind));
+            descriptions.push(description.to_string());
+        }
+    }
+
+    descriptions.extend(rest.iter().map(|kind| match kind {
+        TokenKind::LastChar => end.to_string(),
+        _ => kind.description().to_string(),
+    }));
+
+    descriptions.join(" or ")
+}
+
+/// What the token is called in diagnostics, `end` is the offset right after the data
+pub fn describe_token(source: &[u8], token: &Token, end: usize) -> String {
+    match token.kind() {
+        TokenKind::LastChar => Diagnostic::describe(source, end),
+        TokenKind::Object | TokenKind::Array | TokenKind::Comma | TokenKind::Colon => {
+            Diagnostic::describe(source, *token.location().start())
+        }
+        _ => {
+            let text = String::from_utf8_lossy(source.slice(token.location())).to_string();
+            format!("`{}`", clip_string(text, "…", 24))
+        }
+    }
+}
+
+/// Kinds of tokens that can be used as object keys
+pub const KEY_KINDS: &[TokenKind] = &[
+    TokenKind::String,
+    TokenKind::Number,
+    TokenKind::Boolean,
+    TokenKind::Null,
+    TokenKind::BareWord,
+];
+
+/// Kinds of tokens that can be used as object values or array items
+pub const VALUE_KINDS: &[TokenKind] = &[
+    TokenKind::Object,
+    TokenKind::Array,
+    TokenKind::String,
+    TokenKind::Number,
+    TokenKind::Boolean,
+    TokenKind::Null,
+    TokenKind::BareWord,
+];
+
+/// Lenient number check: a leading `+`, leading zeros and dots without digits on one side are allowed
+fn is_number(sequence: &[u8]) -> bool {
+    let mantissa_finish = sequence
+        .iter()
+        .position(|byte| *byte == b'e' || *byte == b'E')
+        .unwrap_or(sequence.len());
+    let (mantissa, exponent) = sequence.split_at(mantissa_finish);
+
+    let mantissa = mantissa
+        .strip_prefix(b"-")
+        .or_else(|| mantissa.strip_prefix(b"+"))
+        .unwrap_or(mantissa);
+
+    let mut digits = 0;
+    let mut dots = 0;
+    for byte in mantissa.iter() {
+        match byte {
+            b'0'..=b'9' => digits += 1,
+            b'.' => dots += 1,
+            _ => return false,
+        }
+    }
+
+    if digits == 0 || dots > 1 {
+        return false;
+    }
+
+    if let Some(exponent) = exponent.get(1..) {
+        let exponent = exponent
+            .strip_prefix(b"-")
+            .or_else(|| exponent.strip_prefix(b"+"))
+            .unwrap_or(exponent);
+
+        if exponent.is_empty() || !exponent.iter().all(u8::is_ascii_digit) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Classifies a continuous sequence of characters. Anything that is not a number or a literal is a bare word.
+pub fn sequence_kind(sequence: &[u8]) -> TokenKind {
+    match sequence {
+        b"true" | b"false" => TokenKind::Boolean,
+        b"null" => TokenKind::Null,
+        _ if is_number(sequence) => TokenKind::Number,
+        _ => TokenKind::BareWord,
+    }
 }
 
 #[derive(Debug, Clone, ImplHelper)]
@@ -243,17 +547,46 @@ impl<'a> Lexer<'a> {
         anyhow::Ok(next_byt)
     }
 
+    /// Moves to the position, which may be past the end of the data
+    fn move_to(&mut self, pos: usize) {
+        self.state.pos_update(pos);
+        self.state
+            .byt_update(match pos > *self.data.location().finish() {
+                true => None,
+                false => self.data.source().get(pos).cloned(),
+            });
+    }
+
+    /// Skips whitespace and comments
     pub fn skip_spaces(&mut self) -> anyhow::Result<()> {
         loop {
-            if let Some(current_byte) = self.state.byt() {
-                let spaces = &[b' ', b'\t', b'\n', b'\r'];
-                if spaces.contains(current_byte) {
-                    arg_context!(self.read_next_char())?;
-                } else {
-                    break;
+            match self.state.byt() {
+                Some(b' ' | b'\t' | b'\n' | b'\r') => arg_context!(self.read_next_char())?,
+                Some(b'/') => {
+                    let start = *self.state.pos();
+                    let finish = *self.data.location().finish();
+
+                    let Some(comment) = comment_at(self.data.source(), start, finish) else {
+                        break;
+                    };
+
+                    let text = self.data.source().slice(&comment);
+                    if text.starts_with(b"/*") && (text.len() < 4 || !text.ends_with(b"*/")) {
+                        let end = finish + 1;
+
+                        return Err(Diagnostic::new(
+                            self.data.source(),
+                            end,
+                            String::from("`*/`"),
+                            Diagnostic::describe(self.data.source(), end),
+                        )
+                        .related_set(self.data.source(), start, "unclosed comment")
+                        .into());
+                    }
+
+                    self.move_to(comment.finish() + 1);
                 }
-            } else {
-                break;
+                _ => break,
             }
         }
 
@@ -287,59 +620,21 @@ impl<'a> Lexer<'a> {
                 TokenKind::Array,
                 arg_context!(find_block(&mut self.state, &self.data, b'[', b']'))?,
             ),
-            Some(b'"') => {
+            Some(quote @ (b'"' | b'\'')) => {
+                let quote = *quote;
                 let start = self.state.pos().clone();
 
-                let mut search_start = arg_context!(start.checked_add(1))?;
-                if search_start > *self.data.location().finish() {
-                    liab!(
-                        "Could not find '\"' for '\"' at [{}]",
-                        row_col_position(&self.data.source()[..=start])?,
-                    );
-                }
+                let Some(finish) = string_finish(
+                    self.data.source(),
+                    start,
+                    *self.data.location().finish(),
+                    quote,
+                ) else {
+                    return Err(unclosed(&self.data, start, quote, quote).into());
+                };
 
-                let mut finish;
-
-                loop {
-                    if let Some(local_position) = memchr(
-                        b'"',
-                        &self.data.source()[search_start..=*self.data.location.finish()],
-                    ) {
-                        finish = search_start + local_position;
-
-                        let mut backslash_counter = 0;
-
-                        let mut i = finish;
-
-                        loop {
-                            if i > 0 {
-                                i -= 1;
-
-                                if *arg_context!(self.data.source().get(i))? == b'\\' {
-                                    backslash_counter += 1;
-                                } else {
-                                    break;
-                                }
-                            } else {
-                                break;
-                            }
-                        }
-
-                        if backslash_counter % 2 == 0 {
-                            self.state.pos_update(finish);
-                            self.state.byt_update(Some(b'"'));
-
-                            break;
-                        } else {
-                            search_start = arg_context!(finish.checked_add(1))?;
-                        }
-                    } else {
-                        liab!(
-                            "Could not find '\"' for '\"' at [{}]",
-                            row_col_position(&self.data.source()[..=start])?,
-                        );
-                    }
-                }
+                self.state.pos_update(finish);
+                self.state.byt_update(Some(quote));
 
                 Token::new(TokenKind::String, Location::new(start, finish))
             }
@@ -349,7 +644,14 @@ impl<'a> Lexer<'a> {
                 loop {
                     if let Some(next_byt) = arg_context!(self.peek_next_char())? {
                         let terminators = &[b':', b',', b' ', b'\n', b'\t', b'\r'];
-                        if terminators.contains(&next_byt) {
+                        let next_pos = self.state.pos() + 1;
+                        let comment = comment_at(
+                            self.data.source(),
+                            next_pos,
+                            *self.data.location().finish(),
+                        );
+
+                        if terminators.contains(&next_byt) || comment.is_some() {
                             break;
                         } else {
                             arg_context!(self.read_next_char())?;
@@ -360,10 +662,11 @@ impl<'a> Lexer<'a> {
                 }
 
                 let sequence_finish = self.state.pos().clone();
+                let sequence_location = Location::new(sequence_start, sequence_finish);
 
                 Token::new(
-                    TokenKind::Sequence,
-                    Location::new(sequence_start, sequence_finish),
+                    sequence_kind(self.data.source().slice(&sequence_location)),
+                    sequence_location,
                 )
             }
         };
@@ -376,56 +679,72 @@ impl<'a> Lexer<'a> {
     pub fn expect_kinds(&mut self, source: &'a [u8], kinds: &[TokenKind]) -> anyhow::Result<Token> {
         let token = arg_context!(self.next_token())?;
         if !kinds.contains(token.kind()) {
-            liab!(
-                "Expected one of {:?}, but got {:?} at [{}]",
-                kinds,
-                token.kind(),
-                arg_context!(row_col_position(&source[..=*token.location().start()]))?
-            );
+            let end = self.data.location().finish() + 1;
+            let offset = match token.kind() {
+                TokenKind::LastChar => end,
+                _ => *token.location().start(),
+            };
+
+            return Err(Diagnostic::new(
+                source,
+                offset,
+                describe_kinds(kinds, &Diagnostic::describe(source, end)),
+                describe_token(source, &token, end),
+            )
+            .into());
         }
 
         anyhow::Ok(token)
     }
 }
 
-pub fn get_object_items<'a>(source: &'a [u8], location: Location) -> anyhow::Result<Vec<Location>> {
-    let mut items = Vec::default();
+/// Reads the next member of an object, `None` after the last one
+fn next_object_item<'a>(
+    lexer: &mut Lexer<'a>,
+    source: &'a [u8],
+) -> anyhow::Result<Option<Location>> {
+    let key_token =
+        arg_context!(lexer.expect_kinds(source, &[KEY_KINDS, &[TokenKind::LastChar]].concat()))?;
 
-    let mut lexer = Lexer::new(source, location);
+    if key_token.kind() == &TokenKind::LastChar {
+        return anyhow::Ok(None);
+    }
 
-    loop {
-        let key_token = arg_context!(lexer.expect_kinds(
-            &source,
-            &[TokenKind::String, TokenKind::Sequence, TokenKind::LastChar]
-        ))?;
+    arg_context!(lexer.expect_kinds(source, &[TokenKind::Colon]))?;
 
-        if key_token.kind() == &TokenKind::LastChar {
-            break;
-        }
+    let value_token = arg_context!(lexer.expect_kinds(source, VALUE_KINDS))?;
 
-        arg_context!(lexer.expect_kinds(&source, &[TokenKind::Colon]))?;
+    arg_context!(lexer.expect_kinds(source, &[TokenKind::Comma, TokenKind::LastChar]))?;
 
-        let value_token = arg_context!(lexer.expect_kinds(
-            &source,
-            &[
-                TokenKind::String,
-                TokenKind::Sequence,
-                TokenKind::Object,
-                TokenKind::Array
-            ]
-        ))?;
+    anyhow::Ok(Some(Location::new(
+        *key_token.location().start(),
+        *value_token.location().finish(),
+    )))
+}
 
-        items.push(Location::new(
-            key_token.location().start().clone(),
-            value_token.location().finish().clone(),
-        ));
+/// Reads the next item of an array, `None` after the last one
+fn next_array_item<'a>(
+    lexer: &mut Lexer<'a>,
+    source: &'a [u8],
+) -> anyhow::Result<Option<Location>> {
+    let value_token =
+        arg_context!(lexer.expect_kinds(source, &[VALUE_KINDS, &[TokenKind::LastChar]].concat()))?;
 
-        if arg_context!(lexer.expect_kinds(&source, &[TokenKind::Comma, TokenKind::LastChar]))?
-            .kind()
-            == &TokenKind::LastChar
-        {
-            break;
-        }
+    if value_token.kind() == &TokenKind::LastChar {
+        return anyhow::Ok(None);
+    }
+
+    arg_context!(lexer.expect_kinds(source, &[TokenKind::Comma, TokenKind::LastChar]))?;
+
+    anyhow::Ok(Some(*value_token.location()))
+}
+
+pub fn get_object_items<'a>(source: &'a [u8], location: Location) -> anyhow::Result<Vec<Location>> {
+    let mut items = Vec::default();
+    let mut lexer = Lexer::new(source, location);
+
+    while let Some(item) = arg_context!(next_object_item(&mut lexer, source))? {
+        items.push(item);
     }
 
     anyhow::Ok(items)
@@ -433,130 +752,459 @@ pub fn get_object_items<'a>(source: &'a [u8], location: Location) -> anyhow::Res
 
 pub fn get_array_items<'a>(source: &'a [u8], location: Location) -> anyhow::Result<Vec<Location>> {
     let mut items = Vec::default();
-
     let mut lexer = Lexer::new(source, location);
 
-    loop {
-        let value_token = arg_context!(lexer.expect_kinds(
-            &source,
-            &[
-                TokenKind::String,
-                TokenKind::Sequence,
-                TokenKind::Object,
-                TokenKind::Array,
-                TokenKind::LastChar
-            ]
-        ))?;
+    while let Some(item) = arg_context!(next_array_item(&mut lexer, source))? {
+        items.push(item);
+    }
 
-        if value_token.kind() == &TokenKind::LastChar {
-            break;
+    anyhow::Ok(items)
+}
+
+/// Position of the next comma that is not inside a string, a comment or brackets
+fn next_delimiter(source: &[u8], start: usize, finish: usize) -> Option<usize> {
+    let mut depth = 0usize;
+
+    for position in Structure::new(source, Location::new(start, finish)) {
+        match source[position] {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth = depth.saturating_sub(1),
+            b',' if depth == 0 => return Some(position),
+            _ => {}
         }
+    }
 
-        items.push(Location::new(
-            value_token.location().start().clone(),
-            value_token.location().finish().clone(),
-        ));
+    None
+}
+
+/// Index of a member or an item that could not be read, with the mistake
+pub type BrokenItem = (usize, Diagnostic);
+
+/// Members of an object or items of an array, like `get_object_items` and `get_array_items`, that goes on after mistakes.
+/// A broken member reaches up to the next comma outside of strings and brackets, so the ones after it can still be read.
+/// Its location is returned among the others, together with its index and the mistake.
+pub fn get_items_recovering(
+    source: &[u8],
+    location: Location,
+    object: bool,
+) -> anyhow::Result<(Vec<Location>, Vec<BrokenItem>)> {
+    let mut items = Vec::default();
+    let mut errors = Vec::default();
+
+    let finish = *location.finish();
+    let mut start = *location.start();
+
+    while start <= finish {
+        let mut lexer = Lexer::new(source, Location::new(start, finish));
+        let item = match object {
+            true => next_object_item(&mut lexer, source),
+            false => next_array_item(&mut lexer, source),
+        };
+
+        let err = match item {
+            Ok(Some(item)) => {
+                items.push(item);
+                start = *lexer.state().pos();
+                continue;
+            }
+            Ok(None) => break,
+            Err(err) => err,
+        };
+
+        // Only mistakes in the source can be skipped
+        let Some(diagnostic) = Diagnostic::find(&err).cloned() else {
+            return Err(err);
+        };
 
-        if arg_context!(lexer.expect_kinds(&source, &[TokenKind::Comma, TokenKind::LastChar]))?
-            .kind()
-            == &TokenKind::LastChar
-        {
+        let Some(broken_start) = source[start..=finish]
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())
+            .map(|position| start + position)
+        else {
             break;
+        };
+
+        let (broken_finish, next_start) = match next_delimiter(source, broken_start, finish) {
+            // A comma right at the start, for example the second one in `[1,,2]`, is the broken part itself
+            Some(comma) if comma == broken_start => (comma, comma + 1),
+            Some(comma) => (comma - 1, comma + 1),
+            None => (finish, finish + 1),
+        };
+
+        let broken_finish = source[broken_start..=broken_finish]
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map_or(broken_finish, |position| broken_start + position);
+
+        errors.push((items.len(), diagnostic));
+        items.push(Location::new(broken_start, broken_finish));
+        start = next_start;
+    }
+
+    anyhow::Ok((items, errors))
+}
+
+/// Location between the brackets of a block, `None` if the block is empty
+pub fn block_content(source: &[u8], token: &Token) -> anyhow::Result<Option<Location>> {
+    let start = arg_context!(token.location().start().checked_add(1))?;
+    // Tokens of one character at the start of the source have nothing inside
+    let Some(end) = token.location().finish().checked_sub(1) else {
+        return anyhow::Ok(None);
+    };
+
+    if start <= end && end < source.len() {
+        anyhow::Ok(Some(Location::new(start, end)))
+    } else {
+        anyhow::Ok(None)
+    }
+}
+
+/// Name and value of an object member or an array item, ready to become a `Node`
+#[derive(Debug, Clone, ImplHelper)]
+pub struct Blank {
+    #[helper(get)]
+    name: String,
+
+    /// Location of the value if it can be expanded further
+    #[helper(get)]
+    location: Option<Location>,
+
+    /// Kind of the value
+    #[helper(get)]
+    kind: TokenKind,
+
+    /// Location of the name as it is written in the source, without quotes
+    #[helper(get)]
+    name_location: Option<Location>,
+
+    /// Location of the value as it is written in the source, including quotes and brackets
+    #[helper(get)]
+    value_location: Location,
+
+    /// Mistake in the escape sequences of the name, which is then left as it is written
+    #[helper(get)]
+    mistake: Option<String>,
+}
+
+impl Blank {
+    pub fn new(
+        name: String,
+        location: Option<Location>,
+        kind: TokenKind,
+        name_location: Option<Location>,
+        value_location: Location,
+    ) -> Self {
+        Self {
+            name,
+            location,
+            kind,
+            name_location,
+            value_location,
+            mistake: None,
         }
     }
+}
 
-    anyhow::Ok(items)
+/// Decoded string inside the location, or the string as it is written and the mistake in its escape sequences
+pub fn decode_or_raw(
+    source: &[u8],
+    location: &Location,
+) -> anyhow::Result<(String, Option<String>)> {
+    match decode_string(source, location) {
+        Ok(string) => anyhow::Ok((string, None)),
+        Err(err) => match Diagnostic::find(&err) {
+            Some(diagnostic) => anyhow::Ok((
+                String::from_utf8_lossy(source.slice(location)).into_owned(),
+                Some(diagnostic.to_string()),
+            )),
+            None => Err(err),
+        },
+    }
 }
 
-pub fn items_to_vec<'a>(
-    source: &'a [u8],
-    items: &[Location],
-) -> anyhow::Result<VecDeque<(String, Option<Location>)>> {
-    let mut vec = VecDeque::new();
+/// Name and value of the member or item at the location
+pub fn item_to_blank(source: &[u8], item: &Location) -> anyhow::Result<Blank> {
+    let mut lexer = Lexer::new(source, *item);
 
-    for item in items.iter() {
-        let mut lexer = Lexer::new(source, *item);
+    let name;
+    let location;
+    let kind;
+    let name_location;
+    let value_location;
+    let mut mistake = None;
 
-        let name;
-        let location;
+    let token_a = lexer.next_token()?;
 
-        let token_a = lexer.next_token()?;
+    if lexer.next_token()?.kind() == &TokenKind::LastChar {
+        kind = token_a.kind().clone();
+        value_location = *token_a.location();
 
-        if lexer.next_token()?.kind() == &TokenKind::LastChar {
-            match token_a.kind() {
-                TokenKind::Object => {
-                    name = String::from("{...}");
-                    location = Some(Location::new(
-                        token_a.location().start().clone(),
-                        token_a.location().finish().clone(),
-                    ));
-                }
-                TokenKind::Array => {
-                    name = String::from("[...]");
-                    location = Some(Location::new(
-                        token_a.location().start().clone(),
-                        token_a.location().finish().clone(),
-                    ));
-                }
-                TokenKind::String => {
-                    let name_start =
-                        arg_context!(token_a.location().start().checked_add(1))?.clone();
-                    let name_finish =
-                        arg_context!(token_a.location().finish().checked_sub(1))?.clone();
-                    name = if name_start <= name_finish && name_finish < source.len() {
-                        arg_context!(from_utf8(&source[name_start..=name_finish]))?.to_string()
-                    } else {
-                        String::default()
-                    };
-                    location = None;
-                }
-                TokenKind::Sequence => {
-                    name = arg_context!(from_utf8(&source.slice(token_a.location())))?.to_string();
-                    location = None;
-                }
-                _ => {
-                    liab!("Unexpected token: {:?}", token_a)
-                }
+        match token_a.kind() {
+            TokenKind::Object => {
+                name = String::from("{...}");
+                location = Some(Location::new(
+                    token_a.location().start().clone(),
+                    token_a.location().finish().clone(),
+                ));
+                name_location = None;
             }
-        } else {
-            let token_b = lexer.expect_kinds(
-                &source,
-                &[
-                    TokenKind::String,
-                    TokenKind::Sequence,
-                    TokenKind::Object,
-                    TokenKind::Array,
-                ],
-            )?;
-
-            let (name_start, name_finish) = match token_a.kind() {
-                &TokenKind::String => (
-                    arg_context!(token_a.location().start().checked_add(1))?.clone(),
-                    arg_context!(token_a.location().finish().checked_sub(1))?.clone(),
-                ),
-                &TokenKind::Sequence => (
+            TokenKind::Array => {
+                name = String::from("[...]");
+                location = Some(Location::new(
                     token_a.location().start().clone(),
                     token_a.location().finish().clone(),
-                ),
-                _ => {
-                    liab!("Unexpected token: {:?}", token_a)
+                ));
+                name_location = None;
+            }
+            TokenKind::String => {
+                let name_start = arg_context!(token_a.location().start().checked_add(1))?.clone();
+                let name_finish = arg_context!(token_a.location().finish().checked_sub(1))?.clone();
+                if name_start <= name_finish && name_finish < source.len() {
+                    let inner_location = Location::new(name_start, name_finish);
+                    (name, mistake) = arg_context!(decode_or_raw(source, &inner_location))?;
+                    name_location = Some(inner_location);
+                } else {
+                    name = String::default();
+                    name_location = None;
                 }
-            };
+                location = None;
+            }
+            TokenKind::Number | TokenKind::Boolean | TokenKind::Null | TokenKind::BareWord => {
+                name = arg_context!(from_utf8(&source.slice(token_a.location())))?.to_string();
+                location = None;
+                name_location = Some(*token_a.location());
+            }
+            _ => {
+                liab!("Unexpected token: {:?}", token_a)
+            }
+        }
+    } else {
+        let token_b = lexer.expect_kinds(source, VALUE_KINDS)?;
 
-            name = if name_start <= name_finish && name_finish < source.len() {
-                arg_context!(from_utf8(&source[name_start..=name_finish]))?.to_string()
+        let (name_start, name_finish) = match token_a.kind() {
+            &TokenKind::String => (
+                arg_context!(token_a.location().start().checked_add(1))?.clone(),
+                arg_context!(token_a.location().finish().checked_sub(1))?.clone(),
+            ),
+            kind if KEY_KINDS.contains(kind) => (
+                token_a.location().start().clone(),
+                token_a.location().finish().clone(),
+            ),
+            _ => {
+                liab!("Unexpected token: {:?}", token_a)
+            }
+        };
+
+        if name_start <= name_finish && name_finish < source.len() {
+            let inner_location = Location::new(name_start, name_finish);
+            (name, mistake) = if token_a.kind() == &TokenKind::String {
+                arg_context!(decode_or_raw(source, &inner_location))?
             } else {
-                String::default()
+                (
+                    arg_context!(from_utf8(source.slice(&inner_location)))?.to_string(),
+                    None,
+                )
             };
+            name_location = Some(inner_location);
+        } else {
+            name = String::default();
+            name_location = None;
+        }
 
-            location = Some(Location::new(
-                token_b.location().start().clone(),
-                token_b.location().finish().clone(),
-            ));
+        location = Some(Location::new(
+            token_b.location().start().clone(),
+            token_b.location().finish().clone(),
+        ));
+        kind = token_b.kind().clone();
+        value_location = *token_b.location();
+    }
+
+    let mut blank = Blank::new(name, location, kind, name_location, value_location);
+    blank.mistake = mistake;
+
+    anyhow::Ok(blank)
+}
+
+impl Blank {
+    /// Part of the source that could not be read, named after the mistake
+    pub fn broken(diagnostic: &Diagnostic, location: Location) -> Self {
+        Self::new(
+            diagnostic.to_string(),
+            Some(location),
+            TokenKind::Error,
+            None,
+            location,
+        )
+    }
+}
+
+/// Next line of a JSON Lines source from the position on that is not blank, without the whitespace around it,
+/// and the position the line after it starts at
+pub fn next_line(source: &[u8], start: usize) -> Option<(Location, usize)> {
+    let mut start = start;
+
+    while start < source.len() {
+        let finish = memchr(b'\n', &source[start..]).map_or(source.len(), |end| start + end);
+        let line = &source[start..finish];
+
+        if let (Some(first), Some(last)) = (
+            line.iter().position(|byte| !byte.is_ascii_whitespace()),
+            line.iter().rposition(|byte| !byte.is_ascii_whitespace()),
+        ) {
+            return Some((Location::new(start + first, start + last), finish + 1));
         }
 
-        vec.push_back((name, location));
+        start = finish + 1;
+    }
+
+    None
+}
+
+/// Lines of a JSON Lines source that are not blank
+pub fn lines(source: &[u8]) -> impl Iterator<Item = Location> + '_ {
+    let mut start = 0;
+
+    std::iter::from_fn(move || {
+        let (line, next) = next_line(source, start)?;
+        start = next;
+
+        Some(line)
+    })
+}
+
+/// Name and value of the line of a JSON Lines source, which holds a single value
+pub fn line_to_blank(source: &[u8], line: &Location) -> anyhow::Result<Blank> {
+    let mut lexer = Lexer::new(source, *line);
+    let token = arg_context!(lexer.expect_kinds(source, VALUE_KINDS))?;
+
+    let next = arg_context!(lexer.next_token())?;
+    if next.kind() != &TokenKind::LastChar {
+        return Err(Diagnostic::new(
+            source,
+            *next.location().start(),
+            String::from("the end of the line"),
+            describe_token(source, &next, line.finish() + 1),
+        )
+        .into());
+    }
+
+    item_to_blank(source, token.location())
+}
+
+pub fn items_to_vec<'a>(source: &'a [u8], items: &[Location]) -> anyhow::Result<VecDeque<Blank>> {
+    let mut vec = VecDeque::new();
+
+    for item in items.iter() {
+        vec.push_back(arg_context!(item_to_blank(source, item))?);
     }
 
     anyhow::Ok(vec)
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    /// Decodes the whole source as the inside of a string
+    fn decoded(source: &str) -> anyhow::Result<String> {
+        decode_string(
+            source.as_bytes(),
+            &Location::new(0, source.len().saturating_sub(1)),
+        )
+    }
+
+    fn mistake(source: &str) -> String {
+        let err = decoded(source).unwrap_err();
+        Diagnostic::find(&err).unwrap().expected().clone()
+    }
+
+    fn whole(source: &str) -> Location {
+        Location::new(0, source.len() - 1)
+    }
+
+    /// Location between the brackets
+    fn inside(source: &str) -> Location {
+        Location::new(1, source.len() - 2)
+    }
+
+    fn names(source: &str, items: Vec<Location>) -> Vec<String> {
+        items
+            .iter()
+            .map(|item| {
+                item_to_blank(source.as_bytes(), item)
+                    .unwrap()
+                    .name()
+                    .clone()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode_escapes() {
+        assert_eq!(decoded("plain").unwrap(), "plain");
+        assert_eq!(decoded("a\\nb").unwrap(), "a\nb");
+        assert_eq!(decoded("\\\"quoted\\\"").unwrap(), "\"quoted\"");
+        assert_eq!(decoded("caf\\u00e9").unwrap(), "café");
+        assert_eq!(decoded("\\uD83D\\uDE00").unwrap(), "😀");
+        assert_eq!(decoded("\\t\\/\\\\").unwrap(), "\t/\\");
+    }
+
+    #[test]
+    fn decode_mistakes() {
+        assert_eq!(mistake("\\uD800"), "a low surrogate after `\\uD800`");
+        assert_eq!(mistake("\\uD800\\u0041"), "a low surrogate after `\\uD800`");
+        assert_eq!(mistake("\\uDC00"), "a high surrogate before the low one");
+        assert_eq!(
+            mistake("\\q"),
+            "an escape sequence such as `\\n`, `\\\"` or `\\u00e9`"
+        );
+        assert_eq!(mistake("\\u00e"), "four hex digits after `\\u`");
+    }
+
+    #[test]
+    fn decode_or_raw_keeps_the_text() {
+        let source = "a\\qb";
+        let (text, mistake) = decode_or_raw(source.as_bytes(), &whole(source)).unwrap();
+
+        assert_eq!(text, source);
+        assert!(mistake.is_some());
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        let source = "{\n  // first, with a comma\n  \"a\": 1, /* [not] an item */\n  \"b\": 2\n}";
+        let items = get_object_items(source.as_bytes(), inside(source)).unwrap();
+
+        assert_eq!(names(source, items), vec!["a", "b"]);
+        assert_eq!(
+            comments_in(source.as_bytes(), whole(source)),
+            Some(String::from("first, with a comma [not] an item"))
+        );
+        assert_eq!(comments_in(b"[1, 2]", Location::new(0, 5)), None);
+    }
+
+    #[test]
+    fn unclosed_block_comment_takes_the_rest() {
+        let source = "[1] /* open";
+        let mut structure = Structure::new(source.as_bytes(), whole(source));
+        let positions = structure.by_ref().collect::<Vec<usize>>();
+
+        assert_eq!(positions, vec![0, 1, 2, 3]);
+        assert_eq!(structure.comments(), &[Location::new(4, 10)]);
+    }
+
+    #[test]
+    fn single_quoted_strings() {
+        let source = "{'a, b': 'x}', \"c\": 'say \"hi\"'}";
+        let items = get_object_items(source.as_bytes(), inside(source)).unwrap();
+
+        assert_eq!(names(source, items), vec!["a, b", "c"]);
+
+        let source = "['\\'quoted\\'', don't]";
+        let items = get_array_items(source.as_bytes(), inside(source)).unwrap();
+
+        assert_eq!(names(source, items), vec!["'quoted'", "don't"]);
+    }
+}
@@ -1,20 +1,39 @@
 pub mod app;
 pub mod column_model;
 pub mod column_view;
+pub mod config;
+pub mod diagnostic;
+pub mod diff;
 pub mod directional_constraint;
+pub mod edit;
+pub mod error_model;
+pub mod error_view;
 pub mod event;
+pub mod flake_lock;
 pub mod handler;
+pub mod help_model;
+pub mod help_view;
+pub mod input;
+pub mod keymap;
 pub mod lexer;
 pub mod node;
+pub mod output;
 pub mod page_model;
 pub mod page_view;
 pub mod paginator;
 pub mod preferences;
+pub mod prompt;
+pub mod query;
+pub mod reformat;
 pub mod render;
+pub mod search;
+pub mod strict;
 pub mod table_model;
 pub mod table_view;
 pub mod textline_model;
 pub mod textline_view;
+pub mod theme;
 pub mod tui;
 pub mod types;
 pub mod utils;
+pub mod watch;
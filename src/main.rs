@@ -1,85 +1,340 @@
 use anyhow::anyhow;
-use clap::Parser;
-use locker::types::Step;
-use memmap2::Mmap;
+use clap::{Parser, Subcommand};
 use ratatui::{
     backend::{Backend, CrosstermBackend},
+    layout::Rect,
     Terminal,
 };
-use std::{fs::File, io::stderr, path::PathBuf};
+use std::{
+    fs,
+    io::{stderr, stdout, Stderr},
+    path::{Path, PathBuf},
+    rc::Rc,
+};
 
-use wrap_context::{arg_context, raw_context, wohyna};
+use wrap_context::{arg_context, liab, raw_context, wohyna};
 
 mod app;
 mod column_model;
 mod column_view;
+mod config;
+mod diagnostic;
+mod diff;
 mod directional_constraint;
+mod edit;
+mod error_model;
+mod error_view;
 mod event;
+mod flake_lock;
 mod handler;
+mod help_model;
+mod help_view;
+mod input;
+mod keymap;
 mod lexer;
 mod node;
+mod output;
 mod page_model;
 mod page_view;
 mod paginator;
 mod preferences;
+mod prompt;
+mod query;
+mod reformat;
 mod render;
+mod search;
+mod strict;
 mod table_model;
 mod table_view;
 mod textline_model;
 mod textline_view;
+mod theme;
 mod tui;
 mod types;
 mod utils;
+mod watch;
 
-use crate::{app::App, event::EventHandler, tui::Tui, utils::DEBUG_PRINT_LIMIT};
+use crate::{
+    app::{App, Bookmarks, Request},
+    config::Config,
+    diagnostic::Diagnostic,
+    diff::Diff,
+    edit::Splice,
+    error_model::ErrorModel,
+    error_view::ErrorView,
+    event::{Event, EventHandler},
+    flake_lock::{FlakeLock, FlakeMode, FlakeView, Report},
+    input::{input_name, Input, STDIN_NAME},
+    node::{Node, ReadMode},
+    output::{Output, OutputFormat},
+    query::{cursor_path, query_entries, PathQuery},
+    reformat::Layout,
+    strict::check,
+    tui::Tui,
+    types::{Step, ViewModel},
+    utils::{error_message, DEBUG_PRINT_LIMIT},
+    watch::Watcher,
+};
 
 /// JSON reader
 #[derive(Parser)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// File to open. When it is "-" or missing, the data is read from stdin.
     #[arg(value_name = "FILE")]
-    file: PathBuf,
+    file: Option<PathBuf>,
 
-    /// Initial cursor position. Specified as a sequence of positive indices separated by commas, for example: "[0,0,0]", "[3,0,1]", or "4,1,1,0".
+    /// Initial cursor position. Specified as a sequence of positive indices separated by commas, for example: "[0,0,0]", "[3,0,1]", or "4,1,1,0", as a JSON Pointer, for example: "/nodes/nixpkgs/locked", or as a JSONPath, for example: "$.nodes.nixpkgs.locked".
     #[arg(short, long, value_parser = path_parser, default_value = "[0]")]
-    path: Box<[Step]>,
+    path: PathQuery,
+
+    /// Print the value at the path and exit without starting the interface. The path is written the same way as for --path. Exits with code 1 if the path does not exist.
+    #[arg(short, long, visible_alias = "print", value_name = "PATH", value_parser = path_parser)]
+    query: Option<PathQuery>,
+
+    /// With --query, print the children of the value (keys of an object, items of an array) instead of the value itself.
+    #[arg(short, long, requires = "query")]
+    children: bool,
+
+    /// Show the input dependency graph of a flake.lock, starting at its root node, instead of the JSON itself.
+    #[arg(short, long)]
+    graph: bool,
+
+    /// Show the nodes of a flake.lock that are locked from the same source at different revisions. Selecting a group selects its input paths, which can be passed to `nix flake update`.
+    #[arg(long, conflicts_with = "graph")]
+    duplicates: bool,
+
+    /// Print an analysis of a flake.lock and exit without starting the interface.
+    #[arg(long, value_enum, value_name = "REPORT", conflicts_with_all = ["query", "graph", "duplicates"])]
+    report: Option<Report>,
+
+    /// Read the file again when it changes on disk, keeping the cursor and the selection where possible. The file is read into memory instead of being mapped, so it's safe to rewrite it while locker is running.
+    #[arg(short, long, conflicts_with_all = ["query", "report"])]
+    watch: bool,
+
+    /// Format of the printed selection.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Plain)]
+    output: OutputFormat,
+
+    /// Layout of the printed objects and arrays.
+    #[arg(short, long, value_enum, default_value_t = Layout::Keep)]
+    layout: Layout,
+
+    /// Prefix each printed value with its type ("key", "string", "number", "boolean", "null", "bareword", "object" or "array") followed by a tab.
+    #[arg(short, long)]
+    types: bool,
+
+    /// Print names as they are written in the file, without decoding escape sequences.
+    #[arg(short, long)]
+    raw: bool,
+
+    /// Keep reading after mistakes in the file. The parts that can't be read are shown as errors next to the rest of the file, and `E` jumps to the next one.
+    #[arg(long, conflicts_with_all = ["query", "report"])]
+    recover: bool,
+
+    /// Refuse files that are not valid JSON as described by RFC 8259, for example with unquoted keys, bare words or trailing commas. Every mistake is printed and locker exits with code 1.
+    #[arg(long, conflicts_with = "recover")]
+    strict: bool,
+
+    /// Read the data as JSON Lines, one value on each line, and show the lines as the items of an array. Files named *.jsonl or *.ndjson are read this way without it.
+    #[arg(long, conflicts_with_all = ["graph", "duplicates", "report"])]
+    lines: bool,
+
+    /// With --strict, also refuse objects that use the same key twice.
+    #[arg(long, requires = "strict")]
+    unique_keys: bool,
+
+    /// Config file with the key bindings and the theme. Defaults to $XDG_CONFIG_HOME/locker/config.toml, or ~/.config/locker/config.toml.
+    #[arg(long, value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
 
     /// Controls the amount of function argument info shown in tracebacks after a crash. Only useful when debugging.
     #[arg(short, long, default_value = "1")]
     debug_print_limit: usize,
 }
 
-fn path_parser(value: &str) -> anyhow::Result<Box<[Step]>> {
-    let value = value.trim_start_matches('[').trim_end_matches(']');
+#[derive(Subcommand)]
+enum Command {
+    /// Show only the values that were added (green), removed (red) or changed (yellow) between two files. Keys are aligned by name and array items by index.
+    Diff {
+        /// File before the change. When it is "-", the data is read from stdin.
+        old: PathBuf,
 
-    let steps = raw_context!(value
-        .split(',')
-        .map(|word| raw_context!(word.trim().parse::<Step>()))
-        .collect::<anyhow::Result<Box<[Step]>>>())?;
+        /// File after the change.
+        new: PathBuf,
 
-    anyhow::Ok(steps)
+        /// Print which flake.lock inputs moved to another revision, and by how many days, and exit without starting the interface.
+        #[arg(short, long)]
+        summary: bool,
+    },
+
+    /// Check that a file is valid JSON as described by RFC 8259. Every mistake is printed with its line and column. Exits with code 0 if the file is valid, 1 if it's not and 2 if it can't be read.
+    Check {
+        /// File to check. When it is "-", the data is read from stdin.
+        file: PathBuf,
+
+        /// Also refuse objects that use the same key twice, which the RFC only advises against.
+        #[arg(long)]
+        unique_keys: bool,
+
+        /// Check the file as JSON Lines, with one value on each line. Files named *.jsonl or *.ndjson are checked this way without it.
+        #[arg(long)]
+        lines: bool,
+    },
 }
 
-fn exit<B: Backend>(tui: &mut Tui<B>) {
-    if let Err(err) = tui.exit() {
-        eprintln!(
-            "Failed to restore terminal. Run `reset` / `stty sane` or restart your terminal to recover: {}",
-            err
-        );
+fn path_parser(value: &str) -> anyhow::Result<PathQuery> {
+    PathQuery::parse(value).map_err(|err| anyhow!("{}", error_message(&err)))
+}
+
+/// Whether the file is JSON Lines, told by `--lines` or by its name
+fn is_lines(file: Option<&Path>, lines: bool) -> bool {
+    let extension = file.and_then(Path::extension);
+
+    lines || extension.is_some_and(|extension| extension == "jsonl" || extension == "ndjson")
+}
+
+fn read_mode(args: &Cli) -> ReadMode {
+    let file = args.file.as_deref().filter(|file| *file != Path::new("-"));
+
+    ReadMode::default()
+        .recover_set(args.recover)
+        .lines_set(is_lines(file, args.lines))
+}
+
+fn output(args: &Cli) -> Output {
+    Output::new(args.output, args.layout, args.types, args.raw)
+}
+
+/// Whether the input is read as a flake.lock, by its name or because a flake view or report was asked for
+fn is_flake_lock(args: &Cli, file: Option<&Path>) -> bool {
+    let named = file.and_then(Path::file_name) == Some("flake.lock".as_ref());
+
+    named || args.graph || args.duplicates || args.report.is_some()
+}
+
+fn flake_view(args: &Cli) -> FlakeView {
+    match (args.graph, args.duplicates) {
+        (true, _) => FlakeView::Graph,
+        (_, true) => FlakeView::Duplicates,
+        _ => FlakeView::Lock,
     }
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    let args = Cli::parse();
+/// Document that is shown instead of the source in the graph and duplicates views, and the flake.lock mode
+fn flake_document(
+    flake_lock: Option<FlakeLock>,
+    view: FlakeView,
+) -> anyhow::Result<(Option<String>, Option<FlakeMode>)> {
+    match (flake_lock, view) {
+        (Some(flake_lock), FlakeView::Lock) => {
+            anyhow::Ok((None, Some(FlakeMode::new(flake_lock, view))))
+        }
+        (Some(flake_lock), FlakeView::Graph) => {
+            let document = arg_context!(flake_lock.graph_json())?;
+            anyhow::Ok((Some(document), Some(FlakeMode::new(flake_lock, view))))
+        }
+        (Some(flake_lock), FlakeView::Duplicates) => {
+            if flake_lock.duplicates().is_empty() {
+                liab!("No inputs are locked from the same source at different revisions");
+            }
 
-    raw_context!(DEBUG_PRINT_LIMIT
-        .set(args.debug_print_limit)
-        .map_err(|err| wohyna!("Second initialization with value: {:?}", err)))?;
+            let document = flake_lock.duplicates_json();
+            anyhow::Ok((Some(document), Some(FlakeMode::new(flake_lock, view))))
+        }
+        (None, FlakeView::Lock) => anyhow::Ok((None, None)),
+        (None, FlakeView::Graph) => liab!("The input graph can only be shown for flake.lock files"),
+        (None, FlakeView::Duplicates) => {
+            liab!("Duplicate inputs can only be shown for flake.lock files")
+        }
+    }
+}
+
+/// Prints the value at the path, or its children, to stdout
+fn run_query(args: &Cli, query: &PathQuery, source: &[u8]) -> anyhow::Result<()> {
+    let entries = query
+        .resolve(source, read_mode(args))
+        .and_then(|path| query_entries(source, read_mode(args), &path, args.children));
 
+    let entries = match entries {
+        Ok(entries) => entries,
+        Err(err) => {
+            let err = report_error(&input_name(args.file.as_deref()), source, err);
+            eprintln!("locker: {}", error_message(&err));
+            std::process::exit(1);
+        }
+    };
+
+    let output = output(args);
+    arg_context!(output.write(&mut stdout().lock(), source, read_mode(args), &entries))?;
+
+    anyhow::Ok(())
+}
+
+/// Reads the file, or prints why it can't be read and exits
+fn read_or_exit(file: &Path) -> Input {
+    match Input::read(Some(file)) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!(
+                "locker: {}: {}",
+                input_name(Some(file)),
+                error_message(&err)
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Compares the files and shows the differences, or the summary of the moved flake.lock inputs
+async fn run_diff(
+    args: &Cli,
+    config: &Config,
+    old: &Path,
+    new: &Path,
+    summary: bool,
+) -> anyhow::Result<()> {
+    let old_input = read_or_exit(old);
+    let new_input = read_or_exit(new);
+
+    let diff = match Diff::new(old_input.bytes(), new_input.bytes()) {
+        Ok(diff) => diff,
+        Err(err) => {
+            // Diagnostics don't tell which file they are in, so the old one is read on its own first
+            let (name, source, err) = match Diff::new(old_input.bytes(), old_input.bytes()) {
+                Err(old_err) => (input_name(Some(old)), old_input.bytes(), old_err),
+                Ok(_) => (input_name(Some(new)), new_input.bytes(), err),
+            };
+            let err = report_error(&name, source, err);
+            eprintln!("locker: {}: {}", name, error_message(&err));
+            std::process::exit(1);
+        }
+    };
+
+    if summary {
+        print!("{}", diff.summary());
+        return anyhow::Ok(());
+    }
+
+    if diff.is_empty() {
+        eprintln!("locker: the files are the same");
+        return anyhow::Ok(());
+    }
+
+    let file = format!("{} → {}", input_name(Some(old)), input_name(Some(new)));
+    let document = diff.document().to_string();
+    let root = arg_context!(Node::root(document.as_bytes(), 1, ReadMode::default()))?;
+    let path = vec![0].into_boxed_slice();
+
+    run_interface(&file, root, path, None, Some(diff), &output(args), config).await
+}
+
+fn start_interface() -> anyhow::Result<Tui<CrosstermBackend<Stderr>>> {
     let backend = CrosstermBackend::new(stderr());
     let terminal = raw_context!(Terminal::new(backend))?;
-    let terminal_size = arg_context!(terminal.size())?;
     let events = EventHandler::new(250);
 
     let mut tui = Tui::new(terminal, events);
@@ -88,37 +343,487 @@ async fn main() -> anyhow::Result<()> {
         err
     })?;
 
-    let file = arg_context!(File::open(&args.file)).map_err(|err| {
-        exit(&mut tui);
-        err
-    })?;
-    let mmap = unsafe {
-        arg_context!(Mmap::map(&file)).map_err(|err| {
+    anyhow::Ok(tui)
+}
+
+/// Runs the interface for a file, which can be edited and, with `--watch`, is read again every time it changes on disk.
+/// Edits are kept in memory until they are written. The application is started again after each change,
+/// the cursor and the selection are found again by their key paths.
+async fn run_file(
+    args: &Cli,
+    config: &Config,
+    file: &Path,
+    mut input: Input,
+    mut view: (Option<String>, Option<FlakeMode>),
+) -> anyhow::Result<()> {
+    let mut watcher = args.watch.then(|| Watcher::new(file));
+    let mode = read_mode(args);
+    let mut history = Vec::<Splice>::default();
+    // How many edits of the history are in the file, `None` once some of them were undone
+    let mut written = Some(0);
+    // The file changed on disk while there were unsaved edits
+    let mut conflict = false;
+
+    let mut tui = arg_context!(start_interface())?;
+    let mut bookmarks: Option<Bookmarks> = None;
+    let mut notice: Option<String> = None;
+
+    loop {
+        let (document, flake) = view;
+        let source = document
+            .as_ref()
+            .map_or(input.bytes(), |document| document.as_bytes());
+
+        let path = match &bookmarks {
+            Some(bookmarks) => cursor_path(bookmarks.cursor().resolve_nearest(source, mode)),
+            None => match arg_context!(args.path.resolve(source, mode)) {
+                Ok(path) => cursor_path(path),
+                Err(err) => {
+                    return Err(
+                        show_error(&mut tui, &input_name(Some(file)), source, mode, err).await,
+                    )
+                }
+            },
+        }
+        .into_boxed_slice();
+
+        let terminal_size = arg_context!(tui.size()).map_err(|err| {
             exit(&mut tui);
             err
-        })?
-    };
-    let bytes = &mmap[..];
+        })?;
 
-    let mut app = arg_context!(App::new(
-        terminal_size,
-        &args.file,
-        bytes,
-        args.path.clone()
-    ))
-    .map_err(|err| {
-        exit(&mut tui);
-        err
-    })?;
+        let modified = written != Some(history.len());
+        let mut name = input_name(Some(file));
+        if modified {
+            name.push_str(" [+]");
+        }
+        if conflict {
+            name.push_str(" [changed on disk]");
+        }
+
+        let app = arg_context!(Node::root(source, 1, mode)).and_then(|root| {
+            raw_context!(App::new(
+                terminal_size,
+                &name,
+                root,
+                path.clone(),
+                flake.clone(),
+                None::<Diff>,
+                Rc::new(config.theme().clone())
+            ))
+        });
+
+        let mut app = match app {
+            Ok(app) => app,
+            Err(err) => {
+                return Err(show_error(&mut tui, &input_name(Some(file)), source, mode, err).await)
+            }
+        };
+
+        app.set_keymap(config.keymap().clone());
+
+        if let Some(watcher) = &watcher {
+            app.watch(watcher.clone());
+        }
+
+        // Generated views don't map back onto the file, only the file itself can be edited
+        if document.is_none() && file.is_file() {
+            app.enable_editing();
+        }
+
+        if modified {
+            app.mark_modified();
+        }
+
+        if let Some(bookmarks) = &bookmarks {
+            arg_context!(app.restore_selection(bookmarks.selection())).map_err(|err| {
+                exit(&mut tui);
+                err
+            })?;
+        }
+
+        if let Some(notice) = notice.take() {
+            arg_context!(app.notify(notice.clone())).map_err(|err| {
+                exit(&mut tui);
+                err
+            })?;
+        }
+
+        if let Err(err) = arg_context!(app.run(&mut tui).await) {
+            return Err(show_error(&mut tui, &input_name(Some(file)), source, mode, err).await);
+        }
+
+        let Some(request) = app.request().cloned() else {
+            exit(&mut tui);
+            arg_context!(app.print(&output(args)))?;
+
+            return anyhow::Ok(());
+        };
+
+        bookmarks = Some(arg_context!(app.bookmarks()).map_err(|err| {
+            exit(&mut tui);
+            err
+        })?);
+        drop(app);
+
+        // The history only changes once the edited source can be read
+        let mut edited = None;
+        let mut undone = false;
+
+        let changed = match request {
+            Request::Reload => {
+                if modified {
+                    notice = Some(String::from(
+                        "read the file again, the unsaved edits were dropped",
+                    ));
+                }
 
-    arg_context!(app.run(&mut tui).await).map_err(|err| {
+                // Edits made before the file was changed elsewhere can't be undone on top of it
+                history.clear();
+                written = Some(0);
+                conflict = false;
+                watcher = watcher.is_some().then(|| Watcher::new(file));
+
+                Input::read_buffered(file)
+            }
+            Request::Conflict => {
+                conflict = true;
+                notice = Some(String::from(
+                    "the file changed on disk, press R to read it and drop the edits or w to write over it",
+                ));
+                watcher = Some(Watcher::new(file));
+
+                view = (document, flake);
+                continue;
+            }
+            Request::Edit(splice) => {
+                let changed = splice.apply(input.bytes());
+                edited = Some(splice);
+
+                anyhow::Ok(Input::Buffered(changed))
+            }
+            Request::Undo => {
+                let Some(splice) = history.last() else {
+                    notice = Some(String::from("there is nothing to undo"));
+                    view = (document, flake);
+                    continue;
+                };
+                undone = true;
+
+                anyhow::Ok(Input::Buffered(splice.inverse().apply(input.bytes())))
+            }
+            Request::Write => {
+                notice = match fs::write(file, input.bytes()) {
+                    Ok(()) => {
+                        written = Some(history.len());
+                        conflict = false;
+                        Some(format!("wrote {}", file.display()))
+                    }
+                    Err(err) => Some(format!("could not write {}: {}", file.display(), err)),
+                };
+
+                if watcher.is_some() {
+                    watcher = Some(Watcher::new(file));
+                }
+
+                view = (document, flake);
+                continue;
+            }
+        };
+
+        // A broken file keeps the old contents on the screen
+        let changed = changed.and_then(|changed| {
+            let mut root = arg_context!(Node::root(changed.bytes(), 1, mode))?;
+            arg_context!(root.make_children(Step::MIN))?;
+
+            let flake_lock = match is_flake_lock(args, Some(file)) {
+                true => arg_context!(FlakeLock::parse(changed.bytes()))?,
+                false => None,
+            };
+            let view = raw_context!(flake_document(flake_lock, flake_view(args)))?;
+
+            anyhow::Ok((changed, view))
+        });
+
+        match changed {
+            Ok((changed, changed_view)) => {
+                input = changed;
+                view = changed_view;
+
+                if let Some(splice) = edited {
+                    history.push(splice);
+                }
+
+                if undone {
+                    history.pop();
+
+                    if written.is_some_and(|written| written > history.len()) {
+                        written = None;
+                    }
+                }
+            }
+            Err(err) => {
+                notice = Some(format!("could not read the file: {}", error_message(&err)));
+                view = (document, flake);
+            }
+        }
+    }
+}
+
+/// Runs the interface until it's closed and prints the selection
+async fn run_interface(
+    file: &str,
+    root: Node<'_>,
+    path: Box<[Step]>,
+    flake: Option<FlakeMode>,
+    diff: Option<Diff>,
+    output: &Output,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let (source, mode) = (*root.source(), *root.mode());
+
+    let mut tui = arg_context!(start_interface())?;
+    let terminal_size = arg_context!(tui.size()).map_err(|err| {
         exit(&mut tui);
         err
     })?;
 
+    let app = raw_context!(App::new(
+        terminal_size,
+        file,
+        root,
+        path.clone(),
+        flake.clone(),
+        diff.clone(),
+        Rc::new(config.theme().clone())
+    ));
+
+    let mut app = match app {
+        Ok(app) => app,
+        Err(err) => return Err(show_error(&mut tui, file, source, mode, err).await),
+    };
+
+    app.set_keymap(config.keymap().clone());
+
+    if let Err(err) = arg_context!(app.run(&mut tui).await) {
+        return Err(show_error(&mut tui, file, source, mode, err).await);
+    }
+
     exit(&mut tui);
 
-    arg_context!(app.print())?;
+    arg_context!(app.print(output))?;
 
     anyhow::Ok(())
 }
+
+/// Exit code of `locker check` and `--strict` for files that are not valid JSON
+const EXIT_INVALID: i32 = 1;
+
+/// Exit code of `locker check` for files that can't be read at all
+const EXIT_UNREADABLE: i32 = 2;
+
+/// Prints every mistake against RFC 8259 with the lines around it, and the number of them at the end
+fn report_violations(file: &str, source: &[u8], violations: &[Diagnostic], lines: bool) {
+    for violation in violations.iter() {
+        eprint!("{}", violation.report(file, source));
+    }
+
+    let plural = match violations.len() {
+        1 => "",
+        _ => "s",
+    };
+    let format = match lines {
+        true => "JSON Lines",
+        false => "JSON",
+    };
+    eprintln!(
+        "locker: {} is not valid {}, found {} mistake{}",
+        file,
+        format,
+        violations.len(),
+        plural
+    );
+}
+
+/// Checks that the file is valid JSON and exits with the code that tells whether it is
+fn run_check(file: &Path, unique_keys: bool, lines: bool) -> ! {
+    let name = input_name(Some(file));
+    let violations = Input::read(Some(file)).and_then(|input| {
+        check(input.bytes(), unique_keys, lines).map(|violations| (input, violations))
+    });
+
+    match violations {
+        Ok((_, violations)) if violations.is_empty() => std::process::exit(0),
+        Ok((input, violations)) => {
+            report_violations(&name, input.bytes(), &violations, lines);
+            std::process::exit(EXIT_INVALID);
+        }
+        Err(err) => {
+            eprintln!("locker: {}", error_message(&err));
+            std::process::exit(EXIT_UNREADABLE);
+        }
+    }
+}
+
+/// Prints the mistake the error was caused by and exits, other errors are returned
+fn report_error(file: &str, source: &[u8], err: anyhow::Error) -> anyhow::Error {
+    let Some(diagnostic) = Diagnostic::find(&err) else {
+        return err;
+    };
+
+    eprint!("{}", diagnostic.report(file, source));
+    std::process::exit(1);
+}
+
+/// Shows the mistake the error was caused by until a key is pressed, then prints it
+async fn show_error<B: Backend>(
+    tui: &mut Tui<B>,
+    file: &str,
+    source: &[u8],
+    mode: ReadMode,
+    err: anyhow::Error,
+) -> anyhow::Error {
+    let Some(diagnostic) = Diagnostic::find(&err) else {
+        exit(tui);
+        return err;
+    };
+
+    let model = ErrorModel::new(file, source, diagnostic.clone()).recover_set(*mode.recover());
+    let shown = raw_context!(run_error_view(tui, model).await);
+
+    exit(tui);
+
+    match shown {
+        Ok(()) => report_error(file, source, err),
+        Err(view_err) => view_err,
+    }
+}
+
+async fn run_error_view<B: Backend>(tui: &mut Tui<B>, model: ErrorModel) -> anyhow::Result<()> {
+    let size = arg_context!(tui.size())?;
+    let mut view = ViewModel::<ErrorView, ErrorModel>::default()
+        .model_set(model.area_set(Rect::new(0, 0, size.width, size.height)));
+
+    loop {
+        arg_context!(tui.draw(&mut view))?;
+
+        match arg_context!(tui.events.next().await)? {
+            Event::Key(_) => return anyhow::Ok(()),
+            Event::Resize(width, height) => {
+                raw_context!(view.with_model_mut(|model: &mut ErrorModel| {
+                    model.area_update(Rect::new(0, 0, width, height));
+                    anyhow::Ok(())
+                }))?;
+            }
+            Event::Tick | Event::Mouse(_) => {}
+        }
+    }
+}
+
+fn exit<B: Backend>(tui: &mut Tui<B>) {
+    if let Err(err) = tui.exit() {
+        eprintln!(
+            "Failed to restore terminal. Run `reset` / `stty sane` or restart your terminal to recover: {}",
+            err
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Cli::parse();
+
+    raw_context!(DEBUG_PRINT_LIMIT
+        .set(args.debug_print_limit)
+        .map_err(|err| wohyna!("Second initialization with value: {:?}", err)))?;
+
+    // Checking doesn't depend on the config, a broken one shouldn't make it fail
+    if let Some(Command::Check {
+        file,
+        unique_keys,
+        lines,
+    }) = &args.command
+    {
+        run_check(file, *unique_keys, is_lines(Some(file), *lines));
+    }
+
+    // Mistakes in the config are reported before anything is read, so they can't go unnoticed
+    let config = match Config::load(args.config.as_deref()) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("locker: {}", error_message(&err));
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(Command::Diff { old, new, summary }) = &args.command {
+        return run_diff(&args, &config, old, new, *summary).await;
+    }
+
+    let file = args.file.as_deref().filter(|file| *file != Path::new("-"));
+    let mode = read_mode(&args);
+
+    let input = match file {
+        // Files that are watched can be replaced at any moment, so they are not mapped
+        Some(file) if args.watch => arg_context!(Input::read_buffered(file))?,
+        _ if args.watch => liab!("--watch needs a FILE, stdin can't be read again"),
+        _ => arg_context!(Input::read(args.file.as_deref()))?,
+    };
+    let bytes = input.bytes();
+
+    if args.strict {
+        let violations = arg_context!(check(bytes, args.unique_keys, *mode.lines()))?;
+
+        if !violations.is_empty() {
+            report_violations(&input_name(file), bytes, &violations, *mode.lines());
+            std::process::exit(EXIT_INVALID);
+        }
+    }
+
+    // A flake.lock is a single document, not one of the lines
+    let flake_lock = match !*mode.lines() && is_flake_lock(&args, file) {
+        true => arg_context!(FlakeLock::parse(bytes)),
+        false => anyhow::Ok(None),
+    };
+
+    let flake_lock = match flake_lock {
+        Ok(flake_lock) => flake_lock,
+        Err(_) if args.recover => None,
+        Err(err) if args.query.is_none() && args.report.is_none() => {
+            let mut tui = arg_context!(start_interface())?;
+            return Err(show_error(&mut tui, &input_name(file), bytes, mode, err).await);
+        }
+        Err(err) => return Err(report_error(&input_name(file), bytes, err)),
+    };
+
+    if let Some(report) = args.report {
+        let Some(flake_lock) = &flake_lock else {
+            liab!("Reports can only be made for flake.lock files");
+        };
+
+        match report {
+            Report::Duplicates => print!("{}", flake_lock.duplicates_report()),
+        }
+
+        return anyhow::Ok(());
+    }
+
+    let (document, flake) = raw_context!(flake_document(flake_lock, flake_view(&args)))?;
+    let bytes = document
+        .as_ref()
+        .map_or(bytes, |document| document.as_bytes());
+
+    if let Some(query) = &args.query {
+        return run_query(&args, query, bytes);
+    }
+
+    if let Some(file) = file {
+        return run_file(&args, &config, file, input, (document, flake)).await;
+    }
+
+    let path = cursor_path(arg_context!(args.path.resolve(bytes, mode))?).into_boxed_slice();
+
+    let root = arg_context!(Node::root(bytes, 1, mode))?;
+
+    run_interface(STDIN_NAME, root, path, flake, None, &output(&args), &config).await
+}
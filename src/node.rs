@@ -1,15 +1,33 @@
 use impl_helper::ImplHelper;
-use std::{fmt, rc::Rc, str::from_utf8};
+use itertools::Itertools;
+use std::{collections::VecDeque, fmt, rc::Rc, str::from_utf8};
 
 use wrap_context::{arg_context, liab, raw_context};
 
 use crate::{
-    lexer::{get_array_items, get_object_items, items_to_vec, row_col_position, Lexer, TokenKind},
+    diagnostic::Diagnostic,
+    lexer::{
+        comments_in, decode_or_raw, describe_token, get_array_items, get_items_recovering,
+        get_object_items, item_to_blank, line_to_blank, next_line, Blank, BrokenItem, Lexer,
+        TokenKind,
+    },
     paginator::Paginator,
-    types::{Entry, Step},
+    types::{Entry, EntryRole, Path, Step, ValueKind},
     utils::{Location, SliceFromLocation},
 };
 
+/// How the source is read, the same for all nodes of a tree
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ImplHelper)]
+pub struct ReadMode {
+    /// Mistakes become `Error` nodes instead of stopping the reading
+    #[helper(all)]
+    recover: bool,
+
+    /// JSON Lines, the root is an array of the lines
+    #[helper(all)]
+    lines: bool,
+}
+
 #[derive(Clone, ImplHelper, PartialEq)]
 struct TokenInfo {
     #[helper(all)]
@@ -17,31 +35,52 @@ struct TokenInfo {
 
     #[helper(all)]
     items: Vec<Location>,
+
+    /// Indices of the items that could not be read, with their mistakes
+    #[helper(all)]
+    errors: Vec<BrokenItem>,
+
+    #[helper(all)]
+    location: Location,
+
+    /// Whether the items are the lines of a JSON Lines source
+    #[helper(all)]
+    lines: bool,
+
+    /// Position the lines that are not in the items yet start at, `None` once all of them are
+    #[helper(all)]
+    unread: Option<usize>,
 }
 
 impl TokenInfo {
-    pub fn new<'a>(source: &'a [u8], location: Location) -> anyhow::Result<Self> {
+    pub fn new<'a>(source: &'a [u8], location: Location, mode: ReadMode) -> anyhow::Result<Self> {
         let mut lexer = Lexer::new(source, location);
-        let token = arg_context!(lexer.next_token())?;
+        let token = match arg_context!(lexer.next_token()) {
+            Err(err) if *mode.recover() => return Self::recover(source, location, err),
+            token => token?,
+        };
         let kind = token.kind().clone();
+        let mut errors = vec![];
 
         let items = match kind {
-            TokenKind::Object => {
+            TokenKind::Object | TokenKind::Array => {
                 let start = arg_context!(token.location().start().checked_add(1))?;
                 let end = arg_context!(token.location().finish().checked_sub(1))?;
+                let object = kind == TokenKind::Object;
 
                 if start <= end && end < source.len() {
-                    arg_context!(get_object_items(&source, Location::new(start, end)))?
-                } else {
-                    vec![]
-                }
-            }
-            TokenKind::Array => {
-                let start = arg_context!(token.location().start().checked_add(1))?;
-                let end = arg_context!(token.location().finish().checked_sub(1))?;
-
-                if start <= end && end < source.len() {
-                    arg_context!(get_array_items(&source, Location::new(start, end)))?
+                    let inside = Location::new(start, end);
+
+                    match (*mode.recover(), object) {
+                        (true, _) => {
+                            let (items, broken) =
+                                arg_context!(get_items_recovering(source, inside, object))?;
+                            errors = broken;
+                            items
+                        }
+                        (false, true) => arg_context!(get_object_items(source, inside))?,
+                        (false, false) => arg_context!(get_array_items(source, inside))?,
+                    }
                 } else {
                     vec![]
                 }
@@ -56,20 +95,187 @@ impl TokenInfo {
                     vec![]
                 }
             }
-            TokenKind::Sequence => {
+            TokenKind::Number | TokenKind::Boolean | TokenKind::Null | TokenKind::BareWord => {
                 let start = token.location().start().clone();
                 let end = token.location().finish().clone();
 
                 vec![Location::new(start, end)]
             }
-            _ => liab!(
-                "Expected Object, Array, String or Sequence, but got {:?} at [{}]",
-                token.kind(),
-                arg_context!(row_col_position(&source[..=*token.location().start()]))?
-            ),
+            _ => {
+                let end = location.finish() + 1;
+                let err = Diagnostic::new(
+                    source,
+                    *token.location().start(),
+                    String::from("a value"),
+                    describe_token(source, &token, end),
+                )
+                .into();
+
+                return match mode.recover() {
+                    true => Self::recover(source, location, err),
+                    false => Err(err),
+                };
+            }
         };
 
-        anyhow::Ok(Self { kind, items })
+        let location = *token.location();
+
+        anyhow::Ok(Self {
+            kind,
+            items,
+            errors,
+            location,
+            lines: false,
+            unread: None,
+        })
+    }
+
+    /// Root of a JSON Lines source, its lines are found only when they are needed by `read_lines`
+    fn line_root(location: Location) -> Self {
+        Self {
+            kind: TokenKind::Array,
+            items: vec![],
+            errors: vec![],
+            location,
+            lines: true,
+            unread: Some(*location.start()),
+        }
+    }
+
+    /// Finds lines until there are `count` of them or the source ends
+    fn read_lines(&mut self, source: &[u8], count: usize) {
+        while self.items.len() < count {
+            let Some(start) = self.unread else {
+                break;
+            };
+
+            match next_line(source, start) {
+                Some((line, next)) => {
+                    self.items.push(line);
+                    self.unread = Some(next);
+                }
+                None => self.unread = None,
+            }
+        }
+    }
+
+    /// Part of the source that could not be read, its only item is the whole of it.
+    /// When it has a mistake of its own, the item is shown with it, like the items of an array.
+    fn broken(location: Location) -> Self {
+        Self {
+            kind: TokenKind::Error,
+            items: vec![location],
+            errors: vec![],
+            location,
+            lines: false,
+            unread: None,
+        }
+    }
+
+    /// Reads what is possible after the value at the location could not be read.
+    /// A block that is not closed holds everything up to the end of the location, with the mistake as its first item.
+    fn recover(source: &[u8], location: Location, err: anyhow::Error) -> anyhow::Result<Self> {
+        let Some(diagnostic) = Diagnostic::find(&err).cloned() else {
+            return Err(err);
+        };
+
+        let inside = source.slice(&location);
+        let (Some(first), Some(last)) = (
+            inside.iter().position(|byte| !byte.is_ascii_whitespace()),
+            inside.iter().rposition(|byte| !byte.is_ascii_whitespace()),
+        ) else {
+            return Err(err);
+        };
+        let start = location.start() + first;
+        let finish = location.start() + last;
+
+        let kind = match source[start] {
+            b'{' => TokenKind::Object,
+            b'[' => TokenKind::Array,
+            _ => {
+                let location = Location::new(start, finish);
+
+                return anyhow::Ok(Self {
+                    errors: vec![(0, diagnostic)],
+                    ..Self::broken(location)
+                });
+            }
+        };
+
+        let (mut items, mut errors) = match start < finish {
+            true => arg_context!(get_items_recovering(
+                source,
+                Location::new(start + 1, finish),
+                kind == TokenKind::Object
+            ))?,
+            false => (vec![], vec![]),
+        };
+
+        items.insert(0, Location::new(start, start));
+        errors.iter_mut().for_each(|(index, _)| *index += 1);
+        errors.insert(0, (0, diagnostic));
+
+        anyhow::Ok(Self {
+            kind,
+            items,
+            errors,
+            location: Location::new(start, finish),
+            lines: false,
+            unread: None,
+        })
+    }
+
+    /// Comments written before the item
+    fn comment(&self, source: &[u8], index: usize) -> Option<String> {
+        if self.lines {
+            return None;
+        }
+
+        let item = self.items.get(index)?;
+        let gap_start = match index.checked_sub(1) {
+            Some(previous) => self.items[previous].finish() + 1,
+            None => self.location.start() + 1,
+        };
+        let gap_finish = item.start().checked_sub(1)?;
+
+        match gap_start <= gap_finish {
+            true => comments_in(source, Location::new(gap_start, gap_finish)),
+            false => None,
+        }
+    }
+
+    /// Blanks of the items in the location, the broken ones are named after their mistakes
+    fn blanks(
+        &self,
+        source: &[u8],
+        page_location: &Location,
+        mode: ReadMode,
+    ) -> anyhow::Result<VecDeque<Blank>> {
+        let mut blanks = VecDeque::new();
+        let read = |item: &Location| match self.lines {
+            true => arg_context!(line_to_blank(source, item)),
+            false => arg_context!(item_to_blank(source, item)),
+        };
+
+        for index in *page_location.start()..=*page_location.finish() {
+            let item = self.items[index];
+            let broken = self.errors.iter().find(|(broken, _)| *broken == index);
+
+            let blank = match broken {
+                Some((_, diagnostic)) => Blank::broken(diagnostic, item),
+                None => match read(&item) {
+                    Err(err) if *mode.recover() => match Diagnostic::find(&err) {
+                        Some(diagnostic) => Blank::broken(diagnostic, item),
+                        None => return Err(err),
+                    },
+                    blank => blank?,
+                },
+            };
+
+            blanks.push_back(blank);
+        }
+
+        anyhow::Ok(blanks)
     }
 }
 
@@ -77,6 +283,10 @@ impl TokenInfo {
 pub struct Node<'a> {
     #[helper(all)]
     source: &'a [u8],
+
+    #[helper(all)]
+    mode: ReadMode,
+
     location: Option<Location>,
     token_info: Option<TokenInfo>,
 
@@ -102,6 +312,7 @@ impl<'a> fmt::Debug for Node<'a> {
 impl<'a> Node<'a> {
     pub fn new(
         source: &'a [u8],
+        mode: ReadMode,
         location: Option<Location>,
         entry: Rc<Entry>,
         paginator: Paginator,
@@ -110,6 +321,7 @@ impl<'a> Node<'a> {
 
         anyhow::Ok(Self {
             source,
+            mode,
             location,
             token_info,
             entry,
@@ -118,6 +330,70 @@ impl<'a> Node<'a> {
         })
     }
 
+    pub fn root(source: &'a [u8], page_size: usize, mode: ReadMode) -> anyhow::Result<Self> {
+        if source.is_empty() {
+            liab!("Provided file does not contain any data to show");
+        }
+
+        let source_location = Location::new(0, arg_context!(source.len().checked_sub(1))?);
+
+        // The lines are found later, page by page, so even the largest sources are opened right away
+        if *mode.lines() {
+            return Node::new(
+                source,
+                mode,
+                Some(source_location),
+                Rc::new(Entry::new(
+                    String::default(),
+                    Path::default(),
+                    ValueKind::Array,
+                    EntryRole::Root,
+                    None,
+                    Some(source_location),
+                )),
+                Paginator::new(page_size, 0, None),
+            );
+        }
+
+        // Blocks are not lexed here, since it would mean scanning the whole source one more time
+        let mut lexer = Lexer::new(source, source_location);
+        arg_context!(lexer.skip_spaces())?;
+        let value_start = *lexer.state().pos();
+        let kind = match lexer.state().byt() {
+            Some(b'{') => ValueKind::Object,
+            Some(b'[') => ValueKind::Array,
+            _ => {
+                let kind = arg_context!(lexer.next_token())
+                    .and_then(|token| arg_context!(ValueKind::try_from(token.kind())));
+
+                match kind {
+                    Err(_) if *mode.recover() => ValueKind::Error,
+                    kind => kind?,
+                }
+            }
+        };
+        let value_location = Location::new(value_start, *source_location.finish());
+
+        Node::new(
+            source,
+            mode,
+            Some(source_location),
+            Rc::new(Entry::new(
+                String::default(),
+                Path::default(),
+                kind,
+                EntryRole::Root,
+                None,
+                Some(value_location),
+            )),
+            Paginator::new(page_size, 0, None),
+        )
+    }
+
+    pub fn kind(&self) -> &ValueKind {
+        self.entry.kind()
+    }
+
     pub fn kill_children(&mut self) {
         self.children.clear();
     }
@@ -125,12 +401,30 @@ impl<'a> Node<'a> {
     pub fn make_children(&mut self, mut target: Step) -> anyhow::Result<usize> {
         if self.token_info.is_none() {
             if let Some(location) = self.location {
-                let token_info = arg_context!(TokenInfo::new(self.source, location))?;
+                // Broken parts are not read again, the mistake is already in the name of the entry
+                let token_info = match (self.entry.role(), self.entry.kind()) {
+                    (EntryRole::Key | EntryRole::Item, ValueKind::Error) => {
+                        TokenInfo::broken(location)
+                    }
+                    (EntryRole::Root, _) if *self.mode.lines() => TokenInfo::line_root(location),
+                    _ => arg_context!(TokenInfo::new(self.source, location, self.mode))?,
+                };
                 self.paginator.total_update(Some(token_info.items().len()));
                 self.token_info = Some(token_info);
             }
         }
 
+        // Lines are found up to the end of the page after the target, so the next page is known to exist before it's opened
+        if let Some(token_info) = self
+            .token_info
+            .as_mut()
+            .filter(|info| info.unread().is_some())
+        {
+            let pages = (target / self.paginator.size()).saturating_add(2);
+            token_info.read_lines(self.source, pages.saturating_mul(*self.paginator.size()));
+            self.paginator.total_update(Some(token_info.items().len()));
+        }
+
         // TODO maybe add liab if !self.children.is_empty()
         let mut children = vec![];
         let mut path = self.entry.path().clone();
@@ -147,14 +441,31 @@ impl<'a> Node<'a> {
                         self.paginator.start_update(page_location.start().clone());
                         path.push(page_location.start().clone());
 
-                        let mut blanks =
-                            items_to_vec(&self.source, &token_info.items().slice(&page_location))?;
+                        let mut blanks = arg_context!(token_info.blanks(
+                            self.source,
+                            &page_location,
+                            self.mode
+                        ))?;
+
+                        while let Some(blank) = blanks.pop_front() {
+                            let index = *arg_context!(path.last())?;
 
-                        while let Some((name, location)) = blanks.pop_front() {
                             children.push(raw_context!(Node::new(
                                 self.source,
-                                location,
-                                Rc::new(Entry::new(name, path.clone())),
+                                self.mode,
+                                *blank.location(),
+                                Rc::new(
+                                    Entry::new(
+                                        blank.name().clone(),
+                                        path.clone(),
+                                        arg_context!(ValueKind::try_from(blank.kind()))?,
+                                        EntryRole::Key,
+                                        *blank.name_location(),
+                                        Some(*blank.value_location())
+                                    )
+                                    .comment_set(token_info.comment(self.source, index))
+                                    .mistake_set(blank.mistake().clone())
+                                ),
                                 Paginator::new(self.paginator.size().clone(), 0, None)
                             ))?);
 
@@ -164,7 +475,9 @@ impl<'a> Node<'a> {
                         }
                     }
                 }
-                TokenKind::Array => {
+                kind if kind == &TokenKind::Array
+                    || (kind == &TokenKind::Error && !token_info.errors().is_empty()) =>
+                {
                     if !token_info.items().is_empty() {
                         target = target.min(arg_context!(token_info.items().len().checked_sub(1))?);
 
@@ -174,14 +487,31 @@ impl<'a> Node<'a> {
                         self.paginator.start_update(page_location.start().clone());
                         path.push(page_location.start().clone());
 
-                        let mut blanks =
-                            items_to_vec(&self.source, &token_info.items().slice(&page_location))?;
+                        let mut blanks = arg_context!(token_info.blanks(
+                            self.source,
+                            &page_location,
+                            self.mode
+                        ))?;
+
+                        while let Some(blank) = blanks.pop_front() {
+                            let index = *arg_context!(path.last())?;
 
-                        while let Some((name, location)) = blanks.pop_front() {
                             let node = raw_context!(Node::new(
                                 self.source,
-                                location,
-                                Rc::new(Entry::new(name, path.clone())),
+                                self.mode,
+                                *blank.location(),
+                                Rc::new(
+                                    Entry::new(
+                                        blank.name().clone(),
+                                        path.clone(),
+                                        arg_context!(ValueKind::try_from(blank.kind()))?,
+                                        EntryRole::Item,
+                                        *blank.name_location(),
+                                        Some(*blank.value_location())
+                                    )
+                                    .comment_set(token_info.comment(self.source, index))
+                                    .mistake_set(blank.mistake().clone())
+                                ),
                                 Paginator::new(self.paginator.size().clone(), 0, None)
                             ))?;
 
@@ -193,13 +523,32 @@ impl<'a> Node<'a> {
                         }
                     }
                 }
-                TokenKind::String | TokenKind::Sequence => {
+                TokenKind::String
+                | TokenKind::Number
+                | TokenKind::Boolean
+                | TokenKind::Null
+                | TokenKind::BareWord
+                | TokenKind::Error => {
                     target = 0;
 
-                    let name = if let Some(location) = token_info.items().get(0) {
-                        arg_context!(from_utf8(&self.source.slice(location)))?.to_string()
-                    } else {
-                        String::default()
+                    let name_location = token_info.items().first().cloned();
+                    let kind = arg_context!(ValueKind::try_from(token_info.kind()))?;
+                    let (name, mistake) = match (token_info.kind(), &name_location) {
+                        (TokenKind::String, Some(location)) => {
+                            arg_context!(decode_or_raw(self.source, location))?
+                        }
+                        // Broken parts are shown on one line, the way they are written
+                        (TokenKind::Error, Some(location)) => (
+                            String::from_utf8_lossy(self.source.slice(location))
+                                .split_whitespace()
+                                .join(" "),
+                            None,
+                        ),
+                        (_, Some(location)) => (
+                            arg_context!(from_utf8(&self.source.slice(location)))?.to_string(),
+                            None,
+                        ),
+                        (_, None) => (String::default(), None),
                     };
 
                     let first_step = self.paginator.start().clone();
@@ -209,8 +558,19 @@ impl<'a> Node<'a> {
 
                     let node = Node::new(
                         &self.source,
+                        self.mode,
                         None,
-                        Rc::new(Entry::new(name, path.clone())),
+                        Rc::new(
+                            Entry::new(
+                                name,
+                                path.clone(),
+                                kind,
+                                EntryRole::Value,
+                                name_location,
+                                Some(*token_info.location()),
+                            )
+                            .mistake_set(mistake),
+                        ),
                         Paginator::new(self.paginator.size().clone(), 0, Some(0)),
                     )?;
 
@@ -224,4 +584,46 @@ impl<'a> Node<'a> {
 
         anyhow::Ok(target)
     }
+
+    /// Children of the node, page by page.
+    /// Only one page is kept at a time, and none once all of them are visited.
+    pub fn each_child<F>(&mut self, f: &mut F) -> anyhow::Result<()>
+    where
+        F: FnMut(&mut Node<'a>) -> anyhow::Result<()>,
+    {
+        let mut target = Step::MIN;
+
+        loop {
+            self.kill_children();
+            arg_context!(self.make_children(target))?;
+
+            for child in self.children.iter_mut() {
+                raw_context!(f(child))?;
+            }
+
+            let next_target =
+                arg_context!(self.paginator.start().checked_add(self.children.len()))?;
+            if self.children.is_empty() || next_target >= self.paginator.total().unwrap_or(0) {
+                break;
+            }
+
+            target = next_target;
+        }
+
+        self.kill_children();
+
+        anyhow::Ok(())
+    }
+
+    /// Depth-first traversal over all descendants, page by page.
+    /// Children are dropped after being visited, so it should only be used on a detached tree.
+    pub fn walk<F>(&mut self, f: &mut F) -> anyhow::Result<()>
+    where
+        F: FnMut(&Node<'a>) -> anyhow::Result<()>,
+    {
+        raw_context!(self.each_child(&mut |child| {
+            raw_context!(f(child))?;
+            raw_context!(child.walk(f))
+        }))
+    }
 }
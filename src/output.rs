@@ -0,0 +1,306 @@
+use clap::ValueEnum;
+use impl_helper::ImplHelper;
+use std::{io::Write, rc::Rc};
+use wrap_context::{arg_context, raw_context};
+
+use crate::{
+    lexer::decode_or_raw,
+    node::ReadMode,
+    reformat::{reformat, Layout},
+    types::{Entry, EntryRole, ValueKind},
+    utils::{nodes_along_path, Location, SliceFromLocation},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Names, one per line
+    #[default]
+    Plain,
+    /// Names, each terminated by a NUL byte
+    Nul,
+    /// A JSON array of values
+    Json,
+    /// JSON Pointers (RFC 6901)
+    Pointer,
+    /// JSONPath expressions
+    #[value(name = "jsonpath")]
+    JsonPath,
+    /// JSON Pointer and name separated by a tab, one pair per line
+    Tsv,
+    /// Source text of each value, one per line
+    Source,
+}
+
+/// Escapes a string so it can be used as a JSON string literal
+pub fn json_string(string: &str) -> String {
+    let mut result = String::with_capacity(string.len() + 2);
+    result.push('"');
+
+    for character in string.chars() {
+        match character {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            '\u{08}' => result.push_str("\\b"),
+            '\u{0c}' => result.push_str("\\f"),
+            character if character < '\u{20}' => {
+                result.push_str(&format!("\\u{:04x}", u32::from(character)))
+            }
+            character => result.push(character),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+/// Keeps each value on a single line
+fn tsv_field(string: &str) -> String {
+    string
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => {
+            chars.all(|character| character.is_ascii_alphanumeric() || character == '_')
+        }
+        _ => false,
+    }
+}
+
+/// Escapes `~` and `/`, so the name can be used as a single JSON Pointer token
+pub fn escape_pointer_token(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+/// RFC 6901 pointer made of the entries along a path.
+/// Values of keys don't have a step of their own.
+pub fn json_pointer<'e>(entries: impl IntoIterator<Item = &'e Entry>) -> String {
+    let mut pointer = String::default();
+
+    for entry in entries {
+        match entry.role() {
+            EntryRole::Key => {
+                pointer.push('/');
+                pointer.push_str(&escape_pointer_token(entry.name()));
+            }
+            EntryRole::Item => {
+                pointer.push('/');
+                pointer.push_str(&entry.path().last().copied().unwrap_or_default().to_string());
+            }
+            EntryRole::Root | EntryRole::Value => {}
+        }
+    }
+
+    pointer
+}
+
+/// JSONPath expression made of the entries along a path
+pub fn json_path<'e>(entries: impl IntoIterator<Item = &'e Entry>) -> String {
+    let mut path = String::from("$");
+
+    for entry in entries {
+        match entry.role() {
+            EntryRole::Key if is_identifier(entry.name()) => {
+                path.push('.');
+                path.push_str(entry.name());
+            }
+            EntryRole::Key => {
+                path.push_str(&format!(
+                    "['{}']",
+                    entry.name().replace('\\', "\\\\").replace('\'', "\\'")
+                ));
+            }
+            EntryRole::Item => {
+                path.push_str(&format!(
+                    "[{}]",
+                    entry.path().last().copied().unwrap_or_default()
+                ));
+            }
+            EntryRole::Root | EntryRole::Value => {}
+        }
+    }
+
+    path
+}
+
+#[derive(Debug, Clone, Copy, ImplHelper)]
+pub struct Output {
+    #[helper(get)]
+    format: OutputFormat,
+
+    /// Layout of the printed objects and arrays
+    #[helper(get)]
+    layout: Layout,
+
+    /// Prefix each line with the type of the entry
+    #[helper(get)]
+    types: bool,
+
+    /// Print names as they are written in the source
+    #[helper(get)]
+    raw: bool,
+}
+
+/// Text of a part of the source that could not be read, as it is written
+fn broken_source(source: &[u8], entry: &Entry) -> String {
+    match entry.value_location() {
+        Some(location) => String::from_utf8_lossy(source.slice(location)).to_string(),
+        None => entry.name().clone(),
+    }
+}
+
+/// Text of the string or bare word at the location, with escape sequences decoded
+fn scalar_text(source: &[u8], location: &Location, kind: &ValueKind) -> anyhow::Result<String> {
+    match kind {
+        ValueKind::String if location.finish() > &(location.start() + 1) => {
+            let inner = Location::new(location.start() + 1, location.finish() - 1);
+            anyhow::Ok(arg_context!(decode_or_raw(source, &inner))?.0)
+        }
+        ValueKind::String => anyhow::Ok(String::default()),
+        _ => anyhow::Ok(String::from_utf8_lossy(source.slice(location)).to_string()),
+    }
+}
+
+impl Output {
+    pub fn new(format: OutputFormat, layout: Layout, types: bool, raw: bool) -> Self {
+        Self {
+            format,
+            layout,
+            types,
+            raw,
+        }
+    }
+
+    /// Items and values that are objects or arrays don't have real names, so their source is used instead
+    fn name(&self, source: &[u8], entry: &Entry) -> anyhow::Result<String> {
+        match (entry.role(), entry.kind(), entry.name_location()) {
+            // Broken parts are named after the mistake, the text is what can be fixed
+            (_, ValueKind::Error, _) => anyhow::Ok(broken_source(source, entry)),
+            (EntryRole::Item | EntryRole::Value, ValueKind::Object | ValueKind::Array, _) => {
+                self.value_source(source, entry)
+            }
+            (_, _, Some(location)) if self.raw => {
+                anyhow::Ok(String::from_utf8_lossy(source.slice(location)).to_string())
+            }
+            _ => anyhow::Ok(entry.name().clone()),
+        }
+    }
+
+    fn value_source(&self, source: &[u8], entry: &Entry) -> anyhow::Result<String> {
+        match entry.value_location() {
+            Some(location) => reformat(source, location, self.layout),
+            None => anyhow::Ok(entry.name().clone()),
+        }
+    }
+
+    fn json_value(&self, source: &[u8], entry: &Entry) -> anyhow::Result<String> {
+        match (entry.role(), entry.kind()) {
+            (_, ValueKind::Error) => anyhow::Ok(json_string(&broken_source(source, entry))),
+            // A key stands for its member, so the value is printed rather than the name
+            (EntryRole::Key, ValueKind::String | ValueKind::BareWord) => {
+                match entry.value_location() {
+                    Some(location) => anyhow::Ok(json_string(&arg_context!(scalar_text(
+                        source,
+                        location,
+                        entry.kind()
+                    ))?)),
+                    None => anyhow::Ok(json_string(entry.name())),
+                }
+            }
+            (
+                _,
+                ValueKind::Object
+                | ValueKind::Array
+                | ValueKind::Number
+                | ValueKind::Boolean
+                | ValueKind::Null,
+            ) => self.value_source(source, entry),
+            (_, ValueKind::String | ValueKind::BareWord) => anyhow::Ok(json_string(entry.name())),
+        }
+    }
+
+    fn line(&self, source: &[u8], mode: ReadMode, entry: &Entry) -> anyhow::Result<String> {
+        let line = match self.format {
+            OutputFormat::Plain | OutputFormat::Nul | OutputFormat::Json => {
+                arg_context!(self.name(source, entry))?
+            }
+            OutputFormat::Pointer => json_pointer(
+                arg_context!(nodes_along_path(source, mode, entry.path()))?
+                    .iter()
+                    .map(|node| node.entry().as_ref()),
+            ),
+            OutputFormat::JsonPath => json_path(
+                arg_context!(nodes_along_path(source, mode, entry.path()))?
+                    .iter()
+                    .map(|node| node.entry().as_ref()),
+            ),
+            OutputFormat::Tsv => format!(
+                "{}\t{}",
+                json_pointer(
+                    arg_context!(nodes_along_path(source, mode, entry.path()))?
+                        .iter()
+                        .map(|node| node.entry().as_ref())
+                ),
+                tsv_field(&arg_context!(self.name(source, entry))?)
+            ),
+            OutputFormat::Source => arg_context!(self.value_source(source, entry))?,
+        };
+
+        if self.types {
+            anyhow::Ok(format!("{}\t{}", entry.type_name(), line))
+        } else {
+            anyhow::Ok(line)
+        }
+    }
+
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        source: &[u8],
+        mode: ReadMode,
+        entries: &[Rc<Entry>],
+    ) -> anyhow::Result<()> {
+        match self.format {
+            OutputFormat::Json => {
+                let values = raw_context!(entries
+                    .iter()
+                    .map(|entry| self.json_value(source, entry))
+                    .collect::<anyhow::Result<Vec<String>>>())?;
+
+                if self.layout == Layout::Pretty && !values.is_empty() {
+                    let values = values
+                        .iter()
+                        .map(|value| format!("  {}", value.replace('\n', "\n  ")))
+                        .collect::<Vec<String>>();
+
+                    raw_context!(writeln!(writer, "[\n{}\n]", values.join(",\n")))?;
+                } else {
+                    raw_context!(writeln!(writer, "[{}]", values.join(",")))?;
+                }
+            }
+            OutputFormat::Nul => {
+                for entry in entries.iter() {
+                    let line = arg_context!(self.line(source, mode, entry))?;
+                    raw_context!(write!(writer, "{}\0", line))?;
+                }
+            }
+            _ => {
+                for entry in entries.iter() {
+                    let line = arg_context!(self.line(source, mode, entry))?;
+                    raw_context!(writeln!(writer, "{}", line))?;
+                }
+            }
+        }
+
+        anyhow::Ok(())
+    }
+}
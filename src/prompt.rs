@@ -0,0 +1,52 @@
+use impl_helper::ImplHelper;
+
+use crate::types::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptKind {
+    Search,
+    Jump,
+    /// Editing the value under the cursor
+    Edit,
+    /// Editing the key under the cursor
+    Rename,
+    /// New member after the one under the cursor
+    InsertAfter,
+    /// New first member of the object or array under the cursor
+    InsertFirst,
+}
+
+impl PromptKind {
+    pub fn symbol(&self) -> char {
+        match self {
+            PromptKind::Search => '/',
+            PromptKind::Jump => ':',
+            PromptKind::Edit => '=',
+            PromptKind::Rename => '~',
+            PromptKind::InsertAfter | PromptKind::InsertFirst => '+',
+        }
+    }
+}
+
+#[derive(Debug, ImplHelper)]
+pub struct Prompt {
+    #[helper(get)]
+    kind: PromptKind,
+
+    #[helper(all)]
+    input: String,
+
+    /// Cursor position at the moment the prompt was opened
+    #[helper(get)]
+    origin: Path,
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind, origin: Path) -> Self {
+        Self {
+            kind,
+            input: String::default(),
+            origin,
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use std::rc::Rc;
+use wrap_context::{arg_context, liab, raw_context, wohyna};
+
+use crate::{
+    lexer::{
+        block_content, get_array_items, get_object_items, items_to_vec, lines, Lexer, TokenKind,
+    },
+    node::{Node, ReadMode},
+    output::escape_pointer_token,
+    types::{Entry, EntryRole, Path, Step, ValueKind},
+    utils::{nodes_along_path, Location},
+};
+
+const QUERY_PAGE_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryStep {
+    /// Member of an object, in a JSON Pointer it may also be an array index
+    Key(String),
+    /// Item of an array
+    Index(usize),
+}
+
+/// Path given by the user, either as node indices or as key names
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathQuery {
+    /// For example: "[3,0,1]" or "4,1,1,0"
+    Indices(Path),
+    /// For example: "/nodes/nixpkgs/locked" or "$.nodes.nixpkgs.locked"
+    Steps(Vec<QueryStep>),
+}
+
+fn parse_indices(value: &str) -> anyhow::Result<Path> {
+    let value = value.trim_start_matches('[').trim_end_matches(']');
+
+    let steps = raw_context!(value
+        .split(',')
+        .map(|word| raw_context!(word.trim().parse::<Step>()))
+        .collect::<anyhow::Result<Path>>())?;
+
+    anyhow::Ok(steps)
+}
+
+fn parse_pointer(value: &str) -> Vec<QueryStep> {
+    value
+        .split('/')
+        .skip(1)
+        .map(|token| QueryStep::Key(token.replace("~1", "/").replace("~0", "~")))
+        .collect()
+}
+
+fn parse_json_path(value: &str) -> anyhow::Result<Vec<QueryStep>> {
+    let mut steps = vec![];
+    let mut chars = value.chars().skip(1).peekable();
+
+    while let Some(character) = chars.next() {
+        match character {
+            '.' => {
+                let mut key = String::default();
+
+                while let Some(next) = chars.next_if(|next| *next != '.' && *next != '[') {
+                    key.push(next);
+                }
+
+                if key.is_empty() {
+                    liab!("Expected a key after '.' in {:?}", value);
+                }
+
+                steps.push(QueryStep::Key(key));
+            }
+            '[' => match chars.next() {
+                Some(quote @ ('\'' | '"')) => {
+                    let mut key = String::default();
+
+                    loop {
+                        match chars.next() {
+                            Some('\\') => match chars.next() {
+                                Some(escaped) => key.push(escaped),
+                                None => liab!("Unterminated escape sequence in {:?}", value),
+                            },
+                            Some(next) if next == quote => break,
+                            Some(next) => key.push(next),
+                            None => liab!("Unterminated key in {:?}", value),
+                        }
+                    }
+
+                    if chars.next() != Some(']') {
+                        liab!("Expected ']' after the key {:?} in {:?}", key, value);
+                    }
+
+                    steps.push(QueryStep::Key(key));
+                }
+                Some(first) => {
+                    let mut index = String::from(first);
+
+                    while let Some(next) = chars.next_if(|next| *next != ']') {
+                        index.push(next);
+                    }
+
+                    if chars.next() != Some(']') {
+                        liab!("Expected ']' after the index {:?} in {:?}", index, value);
+                    }
+
+                    let index = raw_context!(index.trim().parse::<usize>()).map_err(|_| {
+                        wohyna!(
+                            "Expected an array index, but got {:?} in {:?}",
+                            index,
+                            value
+                        )
+                    })?;
+
+                    steps.push(QueryStep::Index(index));
+                }
+                None => liab!("Unterminated '[' in {:?}", value),
+            },
+            _ => liab!("Unexpected {:?} in {:?}", character, value),
+        }
+    }
+
+    anyhow::Ok(steps)
+}
+
+/// How the already resolved part of the path is shown in the errors
+fn describe(pointer: &str) -> String {
+    if pointer.is_empty() {
+        String::from("the root")
+    } else {
+        format!("{:?}", pointer)
+    }
+}
+
+fn array_index(step: &QueryStep, pointer: &str) -> anyhow::Result<usize> {
+    match step {
+        QueryStep::Index(index) => anyhow::Ok(*index),
+        QueryStep::Key(key) => raw_context!(key.parse::<usize>()).map_err(|_| {
+            wohyna!(
+                "Expected an array index in {}, but got {:?}",
+                describe(pointer),
+                key
+            )
+        }),
+    }
+}
+
+fn out_of_bounds(index: usize, pointer: &str, total: usize) -> anyhow::Error {
+    wohyna!(
+        "Index {} is out of bounds in {}, which has {} items",
+        index,
+        describe(pointer),
+        total
+    )
+}
+
+fn resolve_steps(source: &[u8], mode: ReadMode, steps: &[QueryStep]) -> anyhow::Result<Path> {
+    if source.is_empty() {
+        liab!("Provided file does not contain any data to show");
+    }
+
+    let mut location = Location::new(0, arg_context!(source.len().checked_sub(1))?);
+    let mut path = vec![];
+    let mut pointer = String::default();
+    let mut steps = steps;
+
+    // The root of a JSON Lines source is not written anywhere, its items are the lines
+    if let Some((step, rest)) = steps.split_first().filter(|_| *mode.lines()) {
+        steps = rest;
+
+        let index = arg_context!(array_index(step, &pointer))?;
+        let Some(line) = lines(source).nth(index) else {
+            return Err(out_of_bounds(index, &pointer, lines(source).count()));
+        };
+
+        path.push(index);
+        location = line;
+        pointer = format!("/{}", index);
+    }
+
+    for step in steps.iter() {
+        let mut lexer = Lexer::new(source, location);
+        let token = arg_context!(lexer.next_token())?;
+        let content = arg_context!(block_content(source, &token))?;
+
+        match token.kind() {
+            TokenKind::Object => {
+                let key = match step {
+                    QueryStep::Key(key) => key.clone(),
+                    QueryStep::Index(index) => index.to_string(),
+                };
+
+                let items = match content {
+                    Some(content) => arg_context!(get_object_items(source, content))?,
+                    None => vec![],
+                };
+                let blanks = arg_context!(items_to_vec(source, &items))?;
+
+                let Some(index) = blanks.iter().position(|blank| blank.name() == &key) else {
+                    liab!("Key {:?} does not exist in {}", key, describe(&pointer));
+                };
+
+                path.push(index);
+                location = *blanks[index].value_location();
+                pointer = format!("{}/{}", pointer, escape_pointer_token(&key));
+            }
+            TokenKind::Array => {
+                let index = arg_context!(array_index(step, &pointer))?;
+
+                let items = match content {
+                    Some(content) => arg_context!(get_array_items(source, content))?,
+                    None => vec![],
+                };
+
+                let Some(item) = items.get(index) else {
+                    return Err(out_of_bounds(index, &pointer, items.len()));
+                };
+
+                path.push(index);
+                location = *item;
+                pointer = format!("{}/{}", pointer, index);
+            }
+            _ => liab!(
+                "{} is neither an object nor an array, so it has no {:?}",
+                describe(&pointer),
+                step
+            ),
+        }
+    }
+
+    anyhow::Ok(path)
+}
+
+impl PathQuery {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let value = value.trim();
+
+        if value.starts_with('/') || value.is_empty() {
+            anyhow::Ok(Self::Steps(parse_pointer(value)))
+        } else if value.starts_with('$') {
+            anyhow::Ok(Self::Steps(arg_context!(parse_json_path(value))?))
+        } else {
+            anyhow::Ok(Self::Indices(arg_context!(parse_indices(value))?))
+        }
+    }
+
+    /// Turns the query into node indices. Key names are looked up in the source.
+    pub fn resolve(&self, source: &[u8], mode: ReadMode) -> anyhow::Result<Path> {
+        match self {
+            Self::Indices(path) => anyhow::Ok(path.clone()),
+            Self::Steps(steps) => resolve_steps(source, mode, steps),
+        }
+    }
+}
+
+/// Path written with key names and item indices, so it can be found again after the source changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyPath {
+    steps: Vec<QueryStep>,
+    /// The path ends at the value of a key rather than at the key itself
+    value: bool,
+}
+
+impl KeyPath {
+    /// Path of the last entry, the entries are the ones along the path
+    pub fn new(entries: &[&Entry]) -> Self {
+        let steps = entries
+            .iter()
+            .filter_map(|entry| match entry.role() {
+                EntryRole::Key => Some(QueryStep::Key(entry.name().clone())),
+                EntryRole::Item => Some(QueryStep::Index(
+                    entry.path().last().copied().unwrap_or_default(),
+                )),
+                EntryRole::Root | EntryRole::Value => None,
+            })
+            .collect();
+
+        let value = entries
+            .last()
+            .is_some_and(|entry| entry.role() == &EntryRole::Value);
+
+        Self { steps, value }
+    }
+
+    /// Node indices of the path, or `None` if it's not in the source anymore
+    pub fn resolve(&self, source: &[u8], mode: ReadMode) -> Option<Path> {
+        let mut path = resolve_steps(source, mode, &self.steps).ok()?;
+
+        if self.value {
+            path.push(0);
+        }
+
+        Some(path)
+    }
+
+    /// Node indices of the longest part of the path that is still in the source
+    pub fn resolve_nearest(&self, source: &[u8], mode: ReadMode) -> Path {
+        if let Some(path) = self.resolve(source, mode) {
+            return path;
+        }
+
+        (0..self.steps.len())
+            .rev()
+            .find_map(|length| resolve_steps(source, mode, &self.steps[..length]).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// The cursor can't be placed on the root itself, so paths to the root lead to its first entry
+pub fn cursor_path(mut path: Path) -> Path {
+    if path.is_empty() {
+        path.push(0);
+    }
+
+    path
+}
+
+/// Entry of the value itself, rather than of the key that holds it
+fn value_entry(node: &mut Node) -> anyhow::Result<Rc<Entry>> {
+    let entry = node.entry().clone();
+
+    match (entry.role(), entry.kind()) {
+        (_, ValueKind::Object | ValueKind::Array) => anyhow::Ok(Rc::new(Entry::new(
+            String::default(),
+            entry.path().clone(),
+            *entry.kind(),
+            EntryRole::Value,
+            None,
+            *entry.value_location(),
+        ))),
+        (EntryRole::Item | EntryRole::Value, _) => anyhow::Ok(entry),
+        (EntryRole::Root | EntryRole::Key, _) => {
+            arg_context!(node.make_children(0))?;
+            let value = arg_context!(node.children().first())?;
+
+            anyhow::Ok(value.entry().clone())
+        }
+    }
+}
+
+/// Entries of the children of the node at the path, or the entry of its value.
+/// Only the nodes along the path are built, so it stays cheap on large files.
+pub fn query_entries(
+    source: &[u8],
+    mode: ReadMode,
+    path: &[Step],
+    children: bool,
+) -> anyhow::Result<Vec<Rc<Entry>>> {
+    let mut nodes = arg_context!(nodes_along_path(source, mode, path))?;
+    let node = arg_context!(nodes.last_mut())?;
+
+    if !children {
+        return anyhow::Ok(vec![arg_context!(value_entry(node))?]);
+    }
+
+    let mut entries = vec![];
+    node.paginator_mut().size_update(QUERY_PAGE_SIZE);
+
+    raw_context!(node.each_child(&mut |child| {
+        entries.push(child.entry().clone());
+        anyhow::Ok(())
+    }))?;
+
+    anyhow::Ok(entries)
+}
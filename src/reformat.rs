@@ -0,0 +1,105 @@
+use clap::ValueEnum;
+use wrap_context::{arg_context, liab};
+
+use crate::{
+    lexer::{block_content, get_array_items, get_object_items, Lexer, Token, TokenKind},
+    utils::{Location, SliceFromLocation},
+};
+
+const INDENT: &str = "  ";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Layout {
+    /// Keep the text as it is written in the source
+    #[default]
+    Keep,
+    /// Put each member and item on its own line, indented by two spaces
+    Pretty,
+    /// Remove all whitespace between tokens
+    Minify,
+}
+
+fn push_line_break(layout: Layout, depth: usize, result: &mut String) {
+    if layout == Layout::Pretty {
+        result.push('\n');
+        result.push_str(&INDENT.repeat(depth));
+    }
+}
+
+fn push_token(source: &[u8], token: &Token, result: &mut String) {
+    result.push_str(&String::from_utf8_lossy(source.slice(token.location())));
+}
+
+fn push_value(
+    source: &[u8],
+    token: &Token,
+    layout: Layout,
+    depth: usize,
+    result: &mut String,
+) -> anyhow::Result<()> {
+    let (items, open, close) = match token.kind() {
+        TokenKind::Object => match arg_context!(block_content(source, token))? {
+            Some(content) => (arg_context!(get_object_items(source, content))?, '{', '}'),
+            None => (vec![], '{', '}'),
+        },
+        TokenKind::Array => match arg_context!(block_content(source, token))? {
+            Some(content) => (arg_context!(get_array_items(source, content))?, '[', ']'),
+            None => (vec![], '[', ']'),
+        },
+        _ => {
+            push_token(source, token, result);
+            return anyhow::Ok(());
+        }
+    };
+
+    result.push(open);
+
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            result.push(',');
+        }
+
+        push_line_break(layout, depth + 1, result);
+
+        let mut lexer = Lexer::new(source, *item);
+        let mut value_token = arg_context!(lexer.next_token())?;
+
+        if token.kind() == &TokenKind::Object {
+            push_token(source, &value_token, result);
+            arg_context!(lexer.next_token())?; // Colon
+            result.push_str(if layout == Layout::Pretty { ": " } else { ":" });
+
+            value_token = arg_context!(lexer.next_token())?;
+        }
+
+        arg_context!(push_value(source, &value_token, layout, depth + 1, result))?;
+    }
+
+    if !items.is_empty() {
+        push_line_break(layout, depth, result);
+    }
+
+    result.push(close);
+
+    anyhow::Ok(())
+}
+
+/// Text of the value at the location, laid out according to `layout`.
+/// Tokens themselves are kept as they are written, so bare words and escape sequences survive.
+pub fn reformat(source: &[u8], location: &Location, layout: Layout) -> anyhow::Result<String> {
+    if layout == Layout::Keep {
+        return anyhow::Ok(String::from_utf8_lossy(source.slice(location)).to_string());
+    }
+
+    let mut lexer = Lexer::new(source, *location);
+    let token = arg_context!(lexer.next_token())?;
+
+    if token.kind() == &TokenKind::LastChar {
+        liab!("Expected a value at {:?}, but got nothing", location);
+    }
+
+    let mut result = String::default();
+    arg_context!(push_value(source, &token, layout, 0, &mut result))?;
+
+    anyhow::Ok(result)
+}
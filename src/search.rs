@@ -0,0 +1,137 @@
+use impl_helper::ImplHelper;
+use wrap_context::{arg_context, raw_context};
+
+use crate::{
+    node::{Node, ReadMode},
+    types::{EntryRole, Path, Step, ValueKind},
+};
+
+const SEARCH_PAGE_SIZE: usize = 1024;
+
+/// Smart case: the search is case-insensitive unless the query contains uppercase characters
+fn is_match(name: &str, query: &str) -> bool {
+    if query.chars().any(char::is_uppercase) {
+        name.contains(query)
+    } else {
+        name.to_lowercase().contains(&query.to_lowercase())
+    }
+}
+
+/// Returns paths of all entries whose names match the query, in document order.
+/// The whole source is traversed, including pages and subtrees that are not loaded in the UI.
+pub fn find_matches(source: &[u8], query: &str, mode: ReadMode) -> anyhow::Result<Vec<Path>> {
+    let mut matches = vec![];
+
+    if query.is_empty() {
+        return anyhow::Ok(matches);
+    }
+
+    let mut root = arg_context!(Node::root(source, SEARCH_PAGE_SIZE, mode))?;
+
+    raw_context!(root.walk(&mut |node: &Node| {
+        // Items that are objects or arrays only have placeholder names
+        let is_placeholder = node.entry().role() == &EntryRole::Item
+            && matches!(node.kind(), ValueKind::Object | ValueKind::Array);
+
+        if !is_placeholder && is_match(node.entry().name(), query) {
+            matches.push(node.entry().path().clone());
+        }
+
+        anyhow::Ok(())
+    }))?;
+
+    anyhow::Ok(matches)
+}
+
+/// Returns paths of the parts of the source that could not be read, in document order.
+/// There are only some with `--recover`, otherwise the source is not shown if it has mistakes.
+pub fn find_errors(source: &[u8], mode: ReadMode) -> anyhow::Result<Vec<Path>> {
+    let mut errors = vec![];
+
+    let mut root = arg_context!(Node::root(source, SEARCH_PAGE_SIZE, mode))?;
+
+    raw_context!(root.walk(&mut |node: &Node| {
+        // The text of a broken part is held by a value under the entry with the mistake
+        let broken = node.kind() == &ValueKind::Error && node.entry().role() != &EntryRole::Value;
+
+        if broken || node.entry().mistake().is_some() {
+            errors.push(node.entry().path().clone());
+        }
+
+        anyhow::Ok(())
+    }))?;
+
+    anyhow::Ok(errors)
+}
+
+#[derive(Debug, Default, ImplHelper)]
+pub struct Search {
+    #[helper(get)]
+    query: String,
+
+    #[helper(get)]
+    matches: Vec<Path>,
+
+    #[helper(get)]
+    current: Option<usize>,
+}
+
+impl Search {
+    /// The current match is the first one at or after the `origin`
+    pub fn new(
+        source: &[u8],
+        mode: ReadMode,
+        query: String,
+        origin: &[Step],
+    ) -> anyhow::Result<Self> {
+        let matches = arg_context!(find_matches(source, &query, mode))?;
+
+        let current = if matches.is_empty() {
+            None
+        } else {
+            Some(
+                matches
+                    .iter()
+                    .position(|path| path.as_slice() >= origin)
+                    .unwrap_or(0),
+            )
+        };
+
+        anyhow::Ok(Self {
+            query,
+            matches,
+            current,
+        })
+    }
+
+    pub fn current_path(&self) -> Option<&Path> {
+        self.current.and_then(|index| self.matches.get(index))
+    }
+
+    pub fn next_match(&mut self) -> Option<&Path> {
+        if let Some(current) = self.current {
+            self.current = Some((current + 1) % self.matches.len());
+        }
+
+        self.current_path()
+    }
+
+    pub fn prev_match(&mut self) -> Option<&Path> {
+        if let Some(current) = self.current {
+            self.current = Some(
+                current
+                    .checked_sub(1)
+                    .unwrap_or(self.matches.len().saturating_sub(1)),
+            );
+        }
+
+        self.current_path()
+    }
+
+    pub fn status(&self) -> String {
+        match self.current {
+            Some(current) => format!("match {} of {}", current + 1, self.matches.len()),
+            None => format!("no matches for \"{}\"", self.query),
+        }
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+use wrap_context::arg_context;
+
+use crate::{
+    diagnostic::{Diagnostic, Mark},
+    lexer::{
+        block_content, decode_string, describe_token, lines, Lexer, Structure, Token, TokenKind,
+        KEY_KINDS, VALUE_KINDS,
+    },
+    utils::{Location, SliceFromLocation},
+};
+
+/// Mistakes against RFC 8259 in the source, in the order they are written.
+/// The structure is read by the lenient lexer, everything it lets through is checked here.
+/// Duplicate keys are allowed by the RFC, they are only reported with `unique_keys`.
+/// JSON Lines sources are checked line by line, blank lines are left out.
+pub fn check(source: &[u8], unique_keys: bool, lines: bool) -> anyhow::Result<Vec<Diagnostic>> {
+    let mut checker = Checker {
+        source,
+        unique_keys,
+        lines,
+        violations: vec![],
+    };
+
+    checker.check_utf8();
+    checker.check_comments();
+    arg_context!(checker.check_document())?;
+
+    let mut violations = checker.violations;
+    violations.sort_by_key(|violation| *violation.mark().offset());
+
+    anyhow::Ok(violations)
+}
+
+struct Checker<'a> {
+    source: &'a [u8],
+    unique_keys: bool,
+    lines: bool,
+    violations: Vec<Diagnostic>,
+}
+
+impl<'a> Checker<'a> {
+    fn violation(&mut self, offset: usize, expected: &str, found: String) {
+        self.violations.push(Diagnostic::new(
+            self.source,
+            offset,
+            expected.to_string(),
+            found,
+        ));
+    }
+
+    /// Keeps the mistake the lexer stopped at, the rest of the block it was reading is skipped
+    fn structure<T>(&mut self, result: anyhow::Result<T>) -> anyhow::Result<Option<T>> {
+        match result {
+            Ok(value) => anyhow::Ok(Some(value)),
+            Err(err) => match Diagnostic::find(&err) {
+                Some(diagnostic) => {
+                    self.violations.push(diagnostic.clone());
+                    anyhow::Ok(None)
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    fn describe(&self, token: &Token) -> String {
+        describe_token(self.source, token, self.source.len())
+    }
+
+    fn check_utf8(&mut self) {
+        let mut offset = 0;
+
+        while let Err(err) = std::str::from_utf8(&self.source[offset..]) {
+            let position = offset + err.valid_up_to();
+
+            self.violation(
+                position,
+                "UTF-8 text",
+                format!("the byte 0x{:02x}", self.source[position]),
+            );
+
+            match err.error_len() {
+                Some(length) => offset = position + length,
+                None => break,
+            }
+        }
+    }
+
+    /// The lexer skips comments, so they are looked for separately
+    fn check_comments(&mut self) {
+        let Some(finish) = self.source.len().checked_sub(1) else {
+            return;
+        };
+
+        let mut structure = Structure::new(self.source, Location::new(0, finish));
+        structure.by_ref().for_each(drop);
+
+        for comment in structure.comments().to_vec() {
+            self.violation(*comment.start(), "whitespace", String::from("a comment"));
+        }
+    }
+
+    fn check_document(&mut self) -> anyhow::Result<()> {
+        if self.lines {
+            for line in lines(self.source) {
+                arg_context!(self.check_single(line, "the end of the line"))?;
+            }
+
+            return anyhow::Ok(());
+        }
+
+        let Some(finish) = self.source.len().checked_sub(1) else {
+            self.violation(0, "a value", Diagnostic::describe(self.source, 0));
+            return anyhow::Ok(());
+        };
+
+        arg_context!(self.check_single(Location::new(0, finish), "the end of the input"))
+    }
+
+    /// The location holds one value and nothing after it, `end` is what is expected there
+    fn check_single(&mut self, location: Location, end: &str) -> anyhow::Result<()> {
+        let mut lexer = Lexer::new(self.source, location);
+
+        let Some(token) = self.structure(lexer.expect_kinds(self.source, VALUE_KINDS))? else {
+            return anyhow::Ok(());
+        };
+        arg_context!(self.check_value(&token))?;
+
+        let Some(token) = self.structure(lexer.next_token())? else {
+            return anyhow::Ok(());
+        };
+        if token.kind() != &TokenKind::LastChar {
+            let found = self.describe(&token);
+            self.violation(*token.location().start(), end, found);
+        }
+
+        anyhow::Ok(())
+    }
+
+    fn check_value(&mut self, token: &Token) -> anyhow::Result<()> {
+        match token.kind() {
+            TokenKind::Object => arg_context!(self.check_block(token, true))?,
+            TokenKind::Array => arg_context!(self.check_block(token, false))?,
+            TokenKind::String => arg_context!(self.check_string(token))?,
+            TokenKind::Number => self.check_number(token),
+            TokenKind::Boolean | TokenKind::Null => {}
+            _ => {
+                let found = format!("the bare word {}", self.describe(token));
+                self.violation(*token.location().start(), "a value", found);
+            }
+        }
+
+        anyhow::Ok(())
+    }
+
+    fn check_block(&mut self, token: &Token, object: bool) -> anyhow::Result<()> {
+        let Some(content) = arg_context!(block_content(self.source, token))? else {
+            return anyhow::Ok(());
+        };
+
+        let ending = match object {
+            true => "`}`",
+            false => "`]`",
+        };
+        let first_kinds = match object {
+            true => [KEY_KINDS, &[TokenKind::LastChar]].concat(),
+            false => [VALUE_KINDS, &[TokenKind::LastChar]].concat(),
+        };
+
+        let mut lexer = Lexer::new(self.source, content);
+        let mut keys = HashMap::<String, Mark>::new();
+        let mut comma = None;
+
+        loop {
+            let Some(first) = self.structure(lexer.expect_kinds(self.source, &first_kinds))? else {
+                return anyhow::Ok(());
+            };
+
+            if first.kind() == &TokenKind::LastChar {
+                if let Some(comma) = comma {
+                    self.violation(comma, ending, String::from("a trailing `,`"));
+                }
+
+                return anyhow::Ok(());
+            }
+
+            let value = match object {
+                true => {
+                    arg_context!(self.check_key(&first, &mut keys))?;
+
+                    if self
+                        .structure(lexer.expect_kinds(self.source, &[TokenKind::Colon]))?
+                        .is_none()
+                    {
+                        return anyhow::Ok(());
+                    }
+
+                    match self.structure(lexer.expect_kinds(self.source, VALUE_KINDS))? {
+                        Some(value) => value,
+                        None => return anyhow::Ok(()),
+                    }
+                }
+                false => first,
+            };
+
+            arg_context!(self.check_value(&value))?;
+
+            let Some(separator) = self.structure(
+                lexer.expect_kinds(self.source, &[TokenKind::Comma, TokenKind::LastChar]),
+            )?
+            else {
+                return anyhow::Ok(());
+            };
+
+            match separator.kind() {
+                TokenKind::Comma => comma = Some(*separator.location().start()),
+                _ => return anyhow::Ok(()),
+            }
+        }
+    }
+
+    fn check_key(&mut self, token: &Token, keys: &mut HashMap<String, Mark>) -> anyhow::Result<()> {
+        if token.kind() != &TokenKind::String {
+            let found = self.describe(token);
+            self.violation(*token.location().start(), "a key in double quotes", found);
+            return anyhow::Ok(());
+        }
+
+        arg_context!(self.check_string(token))?;
+
+        if !self.unique_keys {
+            return anyhow::Ok(());
+        }
+
+        let content = string_content(token);
+        let name = match content.start() > content.finish() {
+            true => String::default(),
+            false => match decode_string(self.source, &content) {
+                Ok(name) => name,
+                // Keys that can't be decoded are already reported
+                Err(_) => return anyhow::Ok(()),
+            },
+        };
+
+        let start = *token.location().start();
+        match keys.get(&name) {
+            Some(first) => {
+                let found = format!(
+                    "{} again, it's first used at {}",
+                    self.describe(token),
+                    first
+                );
+                self.violation(start, "a key that is not used yet", found);
+            }
+            None => {
+                keys.insert(name, Mark::new(self.source, start));
+            }
+        }
+
+        anyhow::Ok(())
+    }
+
+    fn check_string(&mut self, token: &Token) -> anyhow::Result<()> {
+        let start = *token.location().start();
+        if self.source[start] == b'\'' {
+            let found = self.describe(token);
+            self.violation(start, "a string in double quotes", found);
+        }
+
+        let content = string_content(token);
+
+        if content.start() > content.finish() {
+            return anyhow::Ok(());
+        }
+
+        for (i, byte) in self.source.slice(&content).iter().enumerate() {
+            if *byte < 0x20 {
+                self.violation(
+                    content.start() + i,
+                    &format!("`{}`", control_escape(*byte)),
+                    format!("the control character U+{:04X}", byte),
+                );
+            }
+        }
+
+        // Only single-quoted strings can escape single quotes
+        let mut escaped = false;
+        for (i, byte) in self.source.slice(&content).iter().enumerate() {
+            match (escaped, byte) {
+                (true, b'\'') => self.violation(
+                    content.start() + i - 1,
+                    "an escape sequence such as `\\n`, `\\\"` or `\\u00e9`",
+                    String::from("`\\'`"),
+                ),
+                (false, b'\\') => {
+                    escaped = true;
+                    continue;
+                }
+                _ => {}
+            }
+
+            escaped = false;
+        }
+
+        // Invalid UTF-8 is already reported for the whole source
+        if std::str::from_utf8(self.source.slice(&content)).is_err() {
+            return anyhow::Ok(());
+        }
+
+        let decoded = decode_string(self.source, &content);
+        self.structure(decoded)?;
+
+        anyhow::Ok(())
+    }
+
+    fn check_number(&mut self, token: &Token) {
+        if let Some(mistake) = number_mistake(self.source.slice(token.location())) {
+            let found = format!("{} {}", self.describe(token), mistake);
+            self.violation(*token.location().start(), "a number", found);
+        }
+    }
+}
+
+/// Location between the quotes, its start is after its finish when the string is empty
+fn string_content(token: &Token) -> Location {
+    Location::new(token.location().start() + 1, token.location().finish() - 1)
+}
+
+/// How the control character has to be written in a string
+fn control_escape(byte: u8) -> String {
+    match byte {
+        b'\n' => String::from("\\n"),
+        b'\r' => String::from("\\r"),
+        b'\t' => String::from("\\t"),
+        0x08 => String::from("\\b"),
+        0x0c => String::from("\\f"),
+        _ => format!("\\u{:04x}", byte),
+    }
+}
+
+/// What the lenient lexer lets through in numbers that RFC 8259 doesn't
+fn number_mistake(number: &[u8]) -> Option<&'static str> {
+    if number.starts_with(b"+") {
+        return Some("with a leading `+`");
+    }
+
+    let number = number.strip_prefix(b"-").unwrap_or(number);
+    let integer_finish = number
+        .iter()
+        .position(|byte| !byte.is_ascii_digit())
+        .unwrap_or(number.len());
+    let (integer, rest) = number.split_at(integer_finish);
+
+    if integer.is_empty() {
+        return Some("without digits before `.`");
+    }
+
+    if integer.len() > 1 && integer.starts_with(b"0") {
+        return Some("with a leading zero");
+    }
+
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        if !fraction.first().is_some_and(u8::is_ascii_digit) {
+            return Some("without digits after `.`");
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn violations(source: &str) -> Vec<String> {
+        violations_of(source.as_bytes(), false, false)
+    }
+
+    fn violations_of(source: &[u8], unique_keys: bool, lines: bool) -> Vec<String> {
+        check(source, unique_keys, lines)
+            .unwrap()
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn valid_json_has_no_violations() {
+        for source in [
+            "{}",
+            "[]",
+            "null",
+            "  \"text\"\n",
+            "{\"a\": [1, -2.5e10, true, false, null], \"b\": {\"c\": \"\\u00e9\\n\"}}",
+            "[0, 0.5, 1E+2, -0]",
+            "{\"a\": 1, \"a\": 2}",
+        ] {
+            assert_eq!(violations(source), Vec::<String>::new(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn lenient_syntax_is_reported() {
+        assert_eq!(
+            violations("{a: 1}"),
+            vec!["1:2: expected a key in double quotes, found `a`"]
+        );
+        assert_eq!(
+            violations("[1, 2,]"),
+            vec!["1:6: expected `]`, found a trailing `,`"]
+        );
+        assert_eq!(
+            violations("['a']"),
+            vec!["1:2: expected a string in double quotes, found `'a'`"]
+        );
+        assert_eq!(
+            violations("[1] // note"),
+            vec!["1:5: expected whitespace, found a comment"]
+        );
+    }
+
+    #[test]
+    fn numbers_are_reported() {
+        assert_eq!(violations("[+1, 01, .5, 1.]").len(), 4);
+        assert_eq!(violations("[1, -1, 1.5, 1e5]"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn strings_are_reported() {
+        assert_eq!(
+            violations("[\"a\\qb\"]"),
+            vec![
+                "1:4: expected an escape sequence such as `\\n`, `\\\"` or `\\u00e9`, found `\\q`"
+            ]
+        );
+        assert_eq!(violations("[\"a\tb\"]").len(), 1);
+        assert_eq!(violations("[\"\\ud800\"]").len(), 1);
+    }
+
+    #[test]
+    fn invalid_utf8_is_reported() {
+        assert_eq!(
+            violations_of(b"[\"\xff\"]", false, false),
+            vec!["1:3: expected UTF-8 text, found the byte 0xff"]
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_only_with_unique_keys() {
+        let source = b"{\"a\": 1, \"a\": 2}";
+
+        assert_eq!(violations_of(source, false, false), Vec::<String>::new());
+        assert_eq!(violations_of(source, true, false).len(), 1);
+    }
+
+    #[test]
+    fn json_lines_are_checked_line_by_line() {
+        let source = b"{\"a\": 1}\n\n[1, 2]\n{\"b\": 2} x\n";
+
+        assert_eq!(violations_of(source, false, true).len(), 1);
+        assert_eq!(
+            violations_of(b"{\"a\": 1}\n[1]\n", false, true),
+            Vec::<String>::new()
+        );
+        assert_eq!(violations_of(b"{\"a\": 1}\n[1]\n", false, false).len(), 1);
+    }
+}
@@ -1,11 +1,11 @@
 use impl_helper::ImplHelper;
-use ratatui::layout::{Constraint, Rect};
+use ratatui::layout::{Constraint, Position, Rect};
 use std::{cell::RefCell, rc::Rc};
 
 use wrap_context::{arg_context, liab, raw_context};
 
 use crate::{
-    column_model::ColumnModel, directional_constraint::DirectionalConstraint,
+    column_model::ColumnModel, directional_constraint::DirectionalConstraint, theme::Theme,
     types::CursorDirection,
 };
 
@@ -69,9 +69,25 @@ pub struct TableModel {
 
     #[helper(all)]
     constraint: DirectionalConstraint,
+
+    #[helper(all)]
+    theme: Rc<Theme>,
 }
 
 impl TableModel {
+    pub fn column_at(
+        &self,
+        position: Position,
+    ) -> anyhow::Result<Option<Rc<RefCell<ColumnModel>>>> {
+        for column in self.show_columns.iter() {
+            if arg_context!(column.try_borrow())?.area().contains(position) {
+                return anyhow::Ok(Some(column.clone()));
+            }
+        }
+
+        anyhow::Ok(None)
+    }
+
     pub fn area_update(&mut self, value: Rect) -> anyhow::Result<&mut Self> {
         self.area = value;
         arg_context!(self.split_area())?;
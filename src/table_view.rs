@@ -21,7 +21,7 @@ impl<'a> TryFrom<&TableModel> for TableView<'a> {
             .iter()
             .map(|column| {
                 let column = arg_context!(column.try_borrow())?;
-                anyhow::Ok(ColumnView::from(&*column))
+                anyhow::Ok(ColumnView::new(&column, model.theme()))
             })
             .collect::<anyhow::Result<Vec<ColumnView>>>()?;
 
@@ -4,7 +4,10 @@ use ratatui::{
     style::Style,
 };
 
-use crate::{directional_constraint::DirectionalConstraint, utils::clip_string};
+use crate::{
+    directional_constraint::DirectionalConstraint,
+    utils::{clip_string, clip_string_start, printable},
+};
 
 type Span = (String, Style);
 
@@ -28,13 +31,39 @@ pub struct TextLineModel {
     #[helper(all)]
     transparent: bool,
 
+    /// Clip the first span from the start, so that its end and the rest of the spans stay visible
+    #[helper(all)]
+    clip_start: bool,
+
     #[helper(all)]
     constraint: DirectionalConstraint,
 }
 
 impl TextLineModel {
     fn make_show_spans(&mut self) {
-        self.show_spans = self.hide_spans.clone();
+        self.show_spans = self
+            .hide_spans
+            .iter()
+            .map(|(content, style)| (printable(content), *style))
+            .collect();
+
+        if self.clip_start {
+            let rest_width = self
+                .show_spans
+                .iter()
+                .skip(1)
+                .map(|(content, _)| content.chars().count())
+                .sum::<usize>();
+
+            if let Some((content, _)) = self.show_spans.first_mut() {
+                *content = clip_string_start(
+                    content.clone(),
+                    "…",
+                    usize::from(self.area.width).saturating_sub(rest_width),
+                );
+            }
+        }
+
         for (content, _) in self.show_spans.iter_mut() {
             *content = clip_string(content.clone(), "…", self.area.width.into());
         }
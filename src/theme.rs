@@ -0,0 +1,278 @@
+use impl_helper::ImplHelper;
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use std::{env, str::FromStr};
+use wrap_context::{arg_context, liab};
+
+use crate::{config::ConfigValue, diff::Change, types::ValueKind};
+
+/// Names of the built-in themes
+pub const THEME_NAMES: [&str; 3] = ["dark", "light", "mono"];
+
+/// Names of the slots that can be changed in the config file
+pub const SLOT_NAMES: [&str; 15] = [
+    "active",
+    "inactive",
+    "selected",
+    "status_bar",
+    "object",
+    "array",
+    "string",
+    "number",
+    "boolean",
+    "null",
+    "bare_word",
+    "error",
+    "added",
+    "removed",
+    "changed",
+];
+
+/// Styles of everything that is drawn in colour
+#[derive(Debug, Clone, PartialEq, Eq, ImplHelper)]
+pub struct Theme {
+    /// Cursor in the column that has the focus
+    #[helper(get)]
+    active: Style,
+
+    /// Cursor in the other columns
+    #[helper(get)]
+    inactive: Style,
+
+    #[helper(get)]
+    selected: Style,
+
+    /// Line at the bottom with the breadcrumb and the file name
+    #[helper(get)]
+    status_bar: Style,
+
+    #[helper(get)]
+    object: Style,
+
+    #[helper(get)]
+    array: Style,
+
+    #[helper(get)]
+    string: Style,
+
+    #[helper(get)]
+    number: Style,
+
+    #[helper(get)]
+    boolean: Style,
+
+    #[helper(get)]
+    null: Style,
+
+    #[helper(get)]
+    bare_word: Style,
+
+    /// Parts of the file that could not be read, only shown with `--recover`
+    #[helper(get)]
+    error: Style,
+
+    /// Values shown by `locker diff`
+    #[helper(get)]
+    added: Style,
+
+    #[helper(get)]
+    removed: Style,
+
+    #[helper(get)]
+    changed: Style,
+}
+
+impl Default for Theme {
+    /// Dark theme, or the monochrome one when `NO_COLOR` is set
+    fn default() -> Self {
+        match env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Self::mono(),
+            _ => Self::dark(),
+        }
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            active: Style::new().bg(Color::Rgb(214, 94, 14)),
+            inactive: Style::new().bg(Color::Rgb(80, 73, 69)),
+            selected: Style::new().on_red(),
+            status_bar: Style::new().white().bg(Color::Rgb(80, 73, 69)),
+            object: Style::new().fg(Color::Rgb(131, 165, 152)),
+            array: Style::new().fg(Color::Rgb(131, 165, 152)),
+            string: Style::new().fg(Color::Rgb(184, 187, 38)),
+            number: Style::new().fg(Color::Rgb(211, 134, 155)),
+            boolean: Style::new().fg(Color::Rgb(142, 192, 124)),
+            null: Style::new().fg(Color::Rgb(146, 131, 116)),
+            bare_word: Style::new().fg(Color::Rgb(250, 189, 47)),
+            error: Style::new().fg(Color::Rgb(251, 73, 52)).bold().underlined(),
+            added: Style::new().fg(Color::Rgb(152, 151, 26)).bold(),
+            removed: Style::new().fg(Color::Rgb(251, 73, 52)).bold(),
+            changed: Style::new().fg(Color::Rgb(250, 189, 47)).bold(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            active: Style::new().bg(Color::Rgb(254, 128, 25)),
+            inactive: Style::new().bg(Color::Rgb(213, 196, 161)),
+            selected: Style::new().bg(Color::Rgb(251, 165, 150)),
+            status_bar: Style::new()
+                .fg(Color::Rgb(60, 56, 54))
+                .bg(Color::Rgb(213, 196, 161)),
+            object: Style::new().fg(Color::Rgb(7, 102, 120)),
+            array: Style::new().fg(Color::Rgb(7, 102, 120)),
+            string: Style::new().fg(Color::Rgb(121, 116, 14)),
+            number: Style::new().fg(Color::Rgb(143, 63, 113)),
+            boolean: Style::new().fg(Color::Rgb(66, 123, 88)),
+            null: Style::new().fg(Color::Rgb(124, 111, 100)),
+            bare_word: Style::new().fg(Color::Rgb(181, 118, 20)),
+            error: Style::new().fg(Color::Rgb(157, 0, 6)).bold().underlined(),
+            added: Style::new().fg(Color::Rgb(121, 116, 14)).bold(),
+            removed: Style::new().fg(Color::Rgb(157, 0, 6)).bold(),
+            changed: Style::new().fg(Color::Rgb(181, 118, 20)).bold(),
+        }
+    }
+
+    /// No colours at all, only bold, underlined and reversed text
+    pub fn mono() -> Self {
+        Self {
+            active: Style::new().reversed(),
+            inactive: Style::new().underlined(),
+            selected: Style::new().bold(),
+            status_bar: Style::new().reversed(),
+            object: Style::new(),
+            array: Style::new(),
+            string: Style::new(),
+            number: Style::new(),
+            boolean: Style::new(),
+            null: Style::new(),
+            bare_word: Style::new(),
+            error: Style::new().bold().italic(),
+            added: Style::new().bold(),
+            removed: Style::new().crossed_out(),
+            changed: Style::new().italic(),
+        }
+    }
+
+    pub fn named(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "dark" => anyhow::Ok(Self::dark()),
+            "light" => anyhow::Ok(Self::light()),
+            "mono" => anyhow::Ok(Self::mono()),
+            _ => liab!(
+                "unknown theme {:?}, the themes are: {}",
+                name,
+                THEME_NAMES.join(", ")
+            ),
+        }
+    }
+
+    pub fn value_style(&self, kind: &ValueKind) -> Style {
+        match kind {
+            ValueKind::Object => self.object,
+            ValueKind::Array => self.array,
+            ValueKind::String => self.string,
+            ValueKind::Number => self.number,
+            ValueKind::Boolean => self.boolean,
+            ValueKind::Null => self.null,
+            ValueKind::BareWord => self.bare_word,
+            ValueKind::Error => self.error,
+        }
+    }
+
+    pub fn change_style(&self, change: &Change) -> Style {
+        match change {
+            Change::Added => self.added,
+            Change::Removed => self.removed,
+            Change::Changed => self.changed,
+        }
+    }
+
+    /// Replaces the style of the slot with the one written in the config file
+    pub fn set(&mut self, slot: &str, value: &ConfigValue) -> anyhow::Result<()> {
+        let ConfigValue::String(text) = value else {
+            liab!("the style has to be a string, got {}", value.kind());
+        };
+
+        let style = arg_context!(parse_style(text))?;
+
+        let slot = match slot {
+            "active" => &mut self.active,
+            "inactive" => &mut self.inactive,
+            "selected" => &mut self.selected,
+            "status_bar" => &mut self.status_bar,
+            "object" => &mut self.object,
+            "array" => &mut self.array,
+            "string" => &mut self.string,
+            "number" => &mut self.number,
+            "boolean" => &mut self.boolean,
+            "null" => &mut self.null,
+            "bare_word" => &mut self.bare_word,
+            "error" => &mut self.error,
+            "added" => &mut self.added,
+            "removed" => &mut self.removed,
+            "changed" => &mut self.changed,
+            _ => liab!(
+                "unknown slot {:?}, the slots are: name, {}",
+                slot,
+                SLOT_NAMES.join(", ")
+            ),
+        };
+
+        *slot = style;
+
+        anyhow::Ok(())
+    }
+}
+
+fn parse_modifier(word: &str) -> Option<Modifier> {
+    match word {
+        "bold" => Some(Modifier::BOLD),
+        "dim" => Some(Modifier::DIM),
+        "italic" => Some(Modifier::ITALIC),
+        "underlined" => Some(Modifier::UNDERLINED),
+        "reversed" => Some(Modifier::REVERSED),
+        "crossed_out" => Some(Modifier::CROSSED_OUT),
+        _ => None,
+    }
+}
+
+/// Reads a style such as `bold #fabd2f on black`, colours are names, `#rrggbb` or 256-colour indices
+pub fn parse_style(text: &str) -> anyhow::Result<Style> {
+    let mut style = Style::new();
+    let mut words = text.split_whitespace();
+
+    while let Some(word) = words.next() {
+        if let Some(modifier) = parse_modifier(word) {
+            style = style.add_modifier(modifier);
+            continue;
+        }
+
+        if word == "none" {
+            continue;
+        }
+
+        let (word, background) = match word {
+            "on" => match words.next() {
+                Some(color) => (color, true),
+                None => liab!("expected a colour after \"on\" in {:?}", text),
+            },
+            _ => (word, false),
+        };
+
+        let Ok(color) = Color::from_str(word) else {
+            liab!(
+                "{:?} is not a colour or a modifier, use a colour name, #rrggbb, a number up to 255 or one of bold, dim, italic, underlined, reversed, crossed_out",
+                word
+            );
+        };
+
+        style = match background {
+            true => style.bg(color),
+            false => style.fg(color),
+        };
+    }
+
+    anyhow::Ok(style)
+}
@@ -1,14 +1,32 @@
-use crossterm::terminal;
+use crossterm::{
+    event::{DisableMouseCapture, EnableMouseCapture},
+    terminal,
+};
 use ratatui::{
     backend::Backend,
     crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+    layout::Size,
     Terminal,
 };
-use std::io;
+use std::io::{self, Write};
 
 use wrap_context::{arg_context, raw_context};
 
-use crate::{app::App, event::EventHandler, render::Render};
+use crate::{event::EventHandler, render::Render, utils::base64_encode};
+
+/// Puts the text into the system clipboard with the OSC 52 escape sequence.
+/// Works over SSH too, as long as the terminal emulator supports it.
+pub fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+    let mut stderr = io::stderr();
+    arg_context!(write!(
+        stderr,
+        "\x1b]52;c;{}\x07",
+        base64_encode(text.as_bytes())
+    ))?;
+    arg_context!(stderr.flush())?;
+
+    anyhow::Ok(())
+}
 
 #[derive(Debug)]
 pub struct Tui<B: Backend> {
@@ -21,8 +39,16 @@ impl<B: Backend> Tui<B> {
         Self { terminal, events }
     }
 
+    pub fn size(&self) -> anyhow::Result<Size> {
+        raw_context!(self.terminal.size())
+    }
+
     pub fn init(&mut self) -> anyhow::Result<()> {
-        arg_context!(crossterm::execute!(io::stderr(), EnterAlternateScreen))?;
+        arg_context!(crossterm::execute!(
+            io::stderr(),
+            EnterAlternateScreen,
+            EnableMouseCapture
+        ))?;
         arg_context!(terminal::enable_raw_mode())?;
 
         let panic_hook = std::panic::take_hook();
@@ -34,13 +60,17 @@ impl<B: Backend> Tui<B> {
         anyhow::Ok(())
     }
 
-    pub fn draw(&mut self, app: &mut App) -> anyhow::Result<()> {
-        raw_context!(self.terminal.draw(|frame| app.render(frame)))?;
+    pub fn draw<R: Render>(&mut self, view: &mut R) -> anyhow::Result<()> {
+        raw_context!(self.terminal.draw(|frame| view.render(frame)))?;
         anyhow::Ok(())
     }
 
     fn reset() -> anyhow::Result<()> {
-        arg_context!(crossterm::execute!(io::stderr(), LeaveAlternateScreen))?;
+        arg_context!(crossterm::execute!(
+            io::stderr(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        ))?;
         arg_context!(terminal::disable_raw_mode())?;
         anyhow::Ok(())
     }
@@ -1,7 +1,7 @@
 use impl_helper::ImplHelper;
-use wrap_context::arg_context;
+use wrap_context::{arg_context, liab};
 
-use crate::render::Render;
+use crate::{lexer::TokenKind, render::Render, utils::Location};
 
 pub type Step = usize; // TODO maybe remove
 pub type Path = Vec<Step>;
@@ -14,18 +14,124 @@ pub enum CursorDirection {
     Left,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, ImplHelper)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Object,
+    Array,
+    String,
+    Number,
+    Boolean,
+    Null,
+    BareWord,
+    /// Part of the source that could not be read
+    Error,
+}
+
+impl ValueKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueKind::Object => "object",
+            ValueKind::Array => "array",
+            ValueKind::String => "string",
+            ValueKind::Number => "number",
+            ValueKind::Boolean => "boolean",
+            ValueKind::Null => "null",
+            ValueKind::BareWord => "bareword",
+            ValueKind::Error => "error",
+        }
+    }
+}
+
+impl TryFrom<&TokenKind> for ValueKind {
+    type Error = anyhow::Error;
+
+    fn try_from(kind: &TokenKind) -> anyhow::Result<Self> {
+        anyhow::Ok(match kind {
+            TokenKind::Object => ValueKind::Object,
+            TokenKind::Array => ValueKind::Array,
+            TokenKind::String => ValueKind::String,
+            TokenKind::Number => ValueKind::Number,
+            TokenKind::Boolean => ValueKind::Boolean,
+            TokenKind::Null => ValueKind::Null,
+            TokenKind::BareWord => ValueKind::BareWord,
+            TokenKind::Error => ValueKind::Error,
+            _ => liab!("Token kind {:?} is not a value", kind),
+        })
+    }
+}
+
+/// What an entry stands for in the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryRole {
+    /// The whole document
+    Root,
+    /// Object member, named after its key
+    Key,
+    /// Array item
+    Item,
+    /// Scalar value of its parent entry
+    Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ImplHelper)]
 pub struct Entry {
     #[helper(all)]
     name: String,
 
     #[helper(all)]
     path: Path,
+
+    /// Kind of the value, for keys it is the kind of the member's value
+    #[helper(all)]
+    kind: ValueKind,
+
+    #[helper(all)]
+    role: EntryRole,
+
+    /// Location of the name as it is written in the source, `name` itself has escape sequences decoded
+    #[helper(all)]
+    name_location: Option<Location>,
+
+    /// Location of the value as it is written in the source, including quotes and brackets
+    #[helper(all)]
+    value_location: Option<Location>,
+
+    /// Comments written right before the member
+    #[helper(all)]
+    comment: Option<String>,
+
+    /// Mistake in the escape sequences of the name, which is then shown as it is written
+    #[helper(all)]
+    mistake: Option<String>,
 }
 
 impl Entry {
-    pub fn new(name: String, path: Path) -> Self {
-        Self { name, path }
+    pub fn new(
+        name: String,
+        path: Path,
+        kind: ValueKind,
+        role: EntryRole,
+        name_location: Option<Location>,
+        value_location: Option<Location>,
+    ) -> Self {
+        Self {
+            name,
+            path,
+            kind,
+            role,
+            name_location,
+            value_location,
+            comment: None,
+            mistake: None,
+        }
+    }
+
+    /// Type name used in the output: keys are always strings in the document, so they get their own name
+    pub fn type_name(&self) -> &'static str {
+        match self.role {
+            EntryRole::Key => "key",
+            _ => self.kind.name(),
+        }
     }
 }
 
@@ -3,12 +3,23 @@ use std::sync::OnceLock;
 use wrap_context::{arg_context, liab};
 
 use crate::{
-    node::Node,
-    types::{CursorDirection, Path, Step},
+    node::{Node, ReadMode},
+    types::{CursorDirection, Entry, Path, Step},
 };
 
 pub static DEBUG_PRINT_LIMIT: OnceLock<usize> = OnceLock::new();
 
+/// Control characters written as escape sequences, so decoded strings can't drive the terminal
+pub fn printable(string: &str) -> String {
+    string
+        .chars()
+        .map(|char| match char.is_control() {
+            true => char.escape_default().to_string(),
+            false => char.to_string(),
+        })
+        .collect()
+}
+
 pub fn clip_string(mut string: String, ending: &str, length: usize) -> String {
     if length == 0 {
         string = String::default();
@@ -26,7 +37,62 @@ pub fn clip_string(mut string: String, ending: &str, length: usize) -> String {
     string
 }
 
-#[derive(Debug, Clone, Copy, ImplHelper, PartialEq)]
+/// Like `clip_string`, but keeps the end of the string and puts `beginning` in front of it
+pub fn clip_string_start(mut string: String, beginning: &str, length: usize) -> String {
+    let count = string.chars().count();
+
+    if length == 0 {
+        string = String::default();
+    } else if count > length {
+        let keep = length.saturating_sub(beginning.chars().count());
+        string = format!(
+            "{}{}",
+            beginning,
+            string.chars().skip(count - keep).collect::<String>()
+        );
+    }
+
+    string
+}
+
+/// The innermost cause of the error without the source location prefix added by `liab!`
+pub fn error_message(err: &anyhow::Error) -> String {
+    let message = err.root_cause().to_string();
+
+    match message
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once("] "))
+    {
+        Some((_, message)) => message.to_string(),
+        None => message,
+    }
+}
+
+pub fn base64_encode(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let triple = chunk.iter().enumerate().fold(0u32, |acc, (i, byte)| {
+            acc | (u32::from(*byte) << (16 - 8 * i))
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(char::from(
+                    ALPHABET[((triple >> (18 - 6 * i)) & 0x3f) as usize],
+                ));
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
+
+#[derive(Debug, Clone, Copy, ImplHelper, PartialEq, Eq)]
 pub struct Location {
     #[helper(all)]
     start: usize,
@@ -91,6 +157,30 @@ pub fn node_by_path<'a>(root: &'a Node, path: &[Step]) -> anyhow::Result<&'a Nod
     anyhow::Ok(current_node)
 }
 
+/// Entries of the loaded nodes along the path, the root is not included
+pub fn entries_in_path<'a>(root: &'a Node, path: &[Step]) -> anyhow::Result<Vec<&'a Entry>> {
+    let mut current_node = root;
+    let mut entries = vec![];
+
+    for (i, step) in path.iter().enumerate() {
+        let shifted_step = arg_context!(step.checked_sub(*current_node.paginator().start()))?;
+
+        if let Some(next_node) = current_node.children().get(shifted_step) {
+            current_node = next_node;
+            entries.push(current_node.entry().as_ref());
+        } else {
+            liab!(
+                "In the path: {:?}, the node at step: {:?} (index: {:?}) does not exist",
+                path,
+                step,
+                i
+            );
+        }
+    }
+
+    anyhow::Ok(entries)
+}
+
 pub fn path_change_location(
     root: &Node,
     path: &[Step],
@@ -401,3 +491,36 @@ pub fn kill_children_at_index(root: &mut Node, path: &[Step], index: usize) -> a
         index
     );
 }
+
+/// Builds a detached chain of nodes from the root to the end of the path, without loading any siblings.
+/// The first node is the root, so the chain is one node longer than the path.
+pub fn nodes_along_path<'a>(
+    source: &'a [u8],
+    mode: ReadMode,
+    path: &[Step],
+) -> anyhow::Result<Vec<Node<'a>>> {
+    let mut current_node = arg_context!(Node::root(source, 1, mode))?;
+    let mut nodes = vec![];
+
+    for (i, step) in path.iter().enumerate() {
+        let target = arg_context!(current_node.make_children(*step))?;
+
+        let next_node = match current_node.children_mut().pop() {
+            Some(next_node) if target == *step => next_node,
+            _ => liab!(
+                "In the path {:?}, the node at step {:?} (index {:?}) does not exist",
+                path,
+                step,
+                i
+            ),
+        };
+
+        current_node.kill_children();
+        nodes.push(current_node);
+        current_node = next_node;
+    }
+
+    nodes.push(current_node);
+
+    anyhow::Ok(nodes)
+}
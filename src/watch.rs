@@ -0,0 +1,40 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+fn stamp(file: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(file).ok()?;
+
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Notices changes of a file by polling its metadata.
+/// Files that are replaced by renaming another file over them are noticed as well.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Watcher {
+    file: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl Watcher {
+    pub fn new(file: &Path) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            stamp: stamp(file),
+        }
+    }
+
+    /// Whether the file has changed since the last check.
+    /// A missing file is not a change, it is probably being replaced.
+    pub fn changed(&mut self) -> bool {
+        match stamp(&self.file) {
+            Some(stamp) if Some(stamp) != self.stamp => {
+                self.stamp = Some(stamp);
+                true
+            }
+            _ => false,
+        }
+    }
+}